@@ -0,0 +1,213 @@
+use candid::{CandidType, Principal};
+use candid::types::Label;
+use candid::types::value::IDLValue;
+use ic_certified_assets::rc_bytes::RcBytes;
+use ic_certified_assets::types::{HttpRequest, HttpResponse, HeaderField};
+use serde_bytes::ByteBuf;
+
+use crate::state::{AuthenticationWithAddress, EvmParams, SvmParams, IcParams, PostSort, PostCursor};
+use crate::verify::checksum_evm_address;
+use crate::{get_posts_page, get_post, get_profile_by_auth};
+
+pub const API_PATH: &str = "/api/v1/";
+
+pub fn is_api_request(req: &HttpRequest) -> bool {
+    req.url.starts_with(API_PATH)
+}
+
+fn get_query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_owned())
+}
+
+// preflight responses carry no data so they are answered without an update call
+pub fn is_preflight_request(req: &HttpRequest) -> bool {
+    req.method == "OPTIONS"
+}
+
+// responses are certified by running the request again as an update call,
+// unless the caller opts in to uncertified responses (only served on the raw domain)
+pub fn is_uncertified_request(req: &HttpRequest) -> bool {
+    let query = req.url.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    get_query_param(query, "uncertified") == Some("true".to_owned())
+}
+
+fn get_cors_headers() -> Vec<HeaderField> {
+    vec![
+        ("Access-Control-Allow-Origin".to_owned(), "*".to_owned()),
+        ("Access-Control-Allow-Methods".to_owned(), "GET, OPTIONS".to_owned()),
+        ("Access-Control-Allow-Headers".to_owned(), "Content-Type".to_owned()),
+    ]
+}
+
+fn build_response(status_code: u16, body: String) -> HttpResponse {
+    let mut headers = get_cors_headers();
+    headers.push(("Content-Type".to_owned(), "application/json".to_owned()));
+    HttpResponse {
+        status_code,
+        headers,
+        body: RcBytes::from(ByteBuf::from(body.as_bytes().to_vec())),
+        streaming_strategy: None,
+        upgrade: None
+    }
+}
+
+fn build_error(status_code: u16, message: &str) -> HttpResponse {
+    build_response(status_code, serde_json::json!({ "error": message }).to_string())
+}
+
+pub fn build_upgrade() -> HttpResponse {
+    HttpResponse {
+        status_code: 200,
+        headers: vec![],
+        body: RcBytes::from(ByteBuf::default()),
+        streaming_strategy: None,
+        upgrade: Some(true)
+    }
+}
+
+// 64 bit and larger numbers are strings as they do not fit in javascript numbers
+fn to_json(value: &IDLValue) -> serde_json::Value {
+    match value {
+        IDLValue::Bool(value) => serde_json::json!(value),
+        IDLValue::Null | IDLValue::None | IDLValue::Reserved => serde_json::Value::Null,
+        IDLValue::Text(value) => serde_json::json!(value),
+        IDLValue::Number(value) => serde_json::json!(value),
+        IDLValue::Float64(value) => serde_json::json!(value),
+        IDLValue::Float32(value) => serde_json::json!(value),
+        IDLValue::Opt(value) => to_json(value),
+        IDLValue::Vec(values) => serde_json::Value::Array(values.iter().map(to_json).collect()),
+        IDLValue::Record(fields) => {
+            if fields.iter().all(|field| matches!(field.id, Label::Unnamed(_))) && !fields.is_empty() {
+                return serde_json::Value::Array(fields.iter().map(|field| to_json(&field.val)).collect());
+            }
+            let entries = fields.iter().map(|field| (get_label_name(&field.id), to_json(&field.val)));
+            serde_json::Value::Object(entries.collect())
+        },
+        IDLValue::Variant(variant) => serde_json::json!({ get_label_name(&variant.0.id): to_json(&variant.0.val) }),
+        IDLValue::Blob(bytes) => serde_json::json!(bytes),
+        IDLValue::Principal(principal) | IDLValue::Service(principal) => serde_json::json!(principal.to_text()),
+        IDLValue::Func(principal, method) => serde_json::json!([principal.to_text(), method]),
+        IDLValue::Int(value) => serde_json::json!(value.to_string()),
+        IDLValue::Nat(value) => serde_json::json!(value.0.to_string()),
+        IDLValue::Nat8(value) => serde_json::json!(value),
+        IDLValue::Nat16(value) => serde_json::json!(value),
+        IDLValue::Nat32(value) => serde_json::json!(value),
+        IDLValue::Nat64(value) => serde_json::json!(value.to_string()),
+        IDLValue::Int8(value) => serde_json::json!(value),
+        IDLValue::Int16(value) => serde_json::json!(value),
+        IDLValue::Int32(value) => serde_json::json!(value),
+        IDLValue::Int64(value) => serde_json::json!(value.to_string()),
+    }
+}
+
+fn get_label_name(label: &Label) -> String {
+    match label {
+        Label::Named(name) => name.to_owned(),
+        Label::Id(id) | Label::Unnamed(id) => id.to_string()
+    }
+}
+
+// encodes the same data the candid methods return
+fn build_json<T: CandidType>(data: &T) -> HttpResponse {
+    match IDLValue::try_from_candid_type(data) {
+        Ok(value) => build_response(200, to_json(&value).to_string()),
+        Err(_) => build_error(500, "Response could not be encoded")
+    }
+}
+
+fn parse_sort(sort: &str) -> Option<PostSort> {
+    match sort {
+        "newest" => Some(PostSort::Newest),
+        "last_activity" => Some(PostSort::LastActivity),
+        "most_liked" => Some(PostSort::MostLiked),
+        "most_replied" => Some(PostSort::MostReplied),
+        _ => None
+    }
+}
+
+// addresses are evm or svm addresses or ic principals
+fn parse_authentication(address: &str) -> Option<AuthenticationWithAddress> {
+    if address.starts_with("0x") {
+        let address = checksum_evm_address(address.to_lowercase());
+        return Some(AuthenticationWithAddress::Evm(EvmParams { address }));
+    }
+    if let Ok(principal) = Principal::from_text(address) {
+        return Some(AuthenticationWithAddress::Ic(IcParams { principal }));
+    }
+    if bs58::decode(address).into_vec().is_ok() {
+        return Some(AuthenticationWithAddress::Svm(SvmParams { address: address.to_owned() }));
+    }
+    None
+}
+
+// GET /api/v1/posts?sort=newest&cursor_value=..&cursor_post_id=..&limit=..
+fn handle_posts(query: &str) -> HttpResponse {
+    let sort_opt = parse_sort(&get_query_param(query, "sort").unwrap_or("newest".to_owned()));
+    if sort_opt.is_none() {
+        return build_error(400, "Invalid sort");
+    }
+
+    let cursor_value_opt = get_query_param(query, "cursor_value").map(|value| value.parse::<u64>());
+    let cursor_post_id_opt = get_query_param(query, "cursor_post_id").map(|value| value.parse::<u64>());
+    let cursor_opt = match (cursor_value_opt, cursor_post_id_opt) {
+        (Some(Ok(value)), Some(Ok(post_id))) => Some(PostCursor { value, post_id }),
+        (None, None) => None,
+        _ => return build_error(400, "Invalid cursor")
+    };
+
+    let limit_opt = match get_query_param(query, "limit").map(|value| value.parse::<u64>()) {
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(_)) => return build_error(400, "Invalid limit"),
+        None => None
+    };
+
+    build_json(&get_posts_page(sort_opt.unwrap(), cursor_opt, limit_opt))
+}
+
+// GET /api/v1/posts/<post_id>
+fn handle_post(post_id: &str) -> HttpResponse {
+    let post_id_result = post_id.parse::<u64>();
+    if post_id_result.is_err() {
+        return build_error(400, "Invalid post id");
+    }
+
+    match get_post(post_id_result.unwrap()) {
+        Ok(post) => build_json(&post),
+        Err(message) => build_error(404, &message)
+    }
+}
+
+// GET /api/v1/profiles/<address>
+fn handle_profile(address: &str) -> HttpResponse {
+    let authentication_opt = parse_authentication(address);
+    if authentication_opt.is_none() {
+        return build_error(400, "Invalid address");
+    }
+
+    match get_profile_by_auth(authentication_opt.unwrap()) {
+        Some(profile) => build_json(&profile),
+        None => build_error(404, "Profile does not exist")
+    }
+}
+
+pub fn handle_api_request(req: &HttpRequest) -> HttpResponse {
+    if is_preflight_request(req) {
+        return build_response(204, String::new());
+    }
+    if req.method != "GET" {
+        return build_error(405, "Method not allowed");
+    }
+
+    let (path, query) = req.url.split_once('?').unwrap_or((&req.url, ""));
+    let segments = path.trim_start_matches(API_PATH).trim_end_matches('/').split('/').collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["posts"] => handle_posts(query),
+        ["posts", post_id] => handle_post(post_id),
+        ["profiles", address] => handle_profile(address),
+        _ => build_error(404, "Not found")
+    }
+}
@@ -0,0 +1,388 @@
+use candid::{candid_method, Principal};
+use ic_cdk::{update, query};
+use ic_certified_assets::state_machine::BATCH_EXPIRY_NANOS;
+use ic_certified_assets::types::{BatchOperation, CommitBatchArguments, CreateAssetArguments, CreateChunkArg, DeleteAssetArguments, SetAssetContentArguments};
+use serde_bytes::ByteBuf;
+
+use crate::state::{State, STATE, Attachment, AttachmentUpload, AttachmentResponse, Permission};
+use crate::utils::uuid;
+use crate::roles::caller_has_permission;
+
+// uploads are kept when the frontend assets are replaced on upgrade
+pub const UPLOADS_PATH: &str = "/uploads/";
+const MAX_ATTACHMENT_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PROFILE_ATTACHMENTS_SIZE: u64 = 10 * 1024 * 1024;
+// uploads are saved with the assets in the candid snapshot on upgrade,
+// so the total stays small enough to be serialized and restored within the upgrade limits
+const MAX_TOTAL_ATTACHMENTS_SIZE: u64 = 64 * 1024 * 1024;
+const ALLOWED_CONTENT_TYPES: [&str; 5] = ["image/png", "image/jpeg", "image/gif", "image/webp", "application/pdf"];
+
+// the declared content type must match the file signature so other files are not served as images
+fn has_content_signature(content_type: &str, content: &[u8]) -> bool {
+    match content_type {
+        "image/png" => content.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => content.starts_with(b"\xff\xd8\xff"),
+        "image/gif" => content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a"),
+        "image/webp" => content.starts_with(b"RIFF") && content.get(8..12) == Some(b"WEBP".as_slice()),
+        "application/pdf" => content.starts_with(b"%PDF-"),
+        _ => false
+    }
+}
+
+fn get_attachment_key(attachment_id: u64) -> String {
+    format!("{}{}", UPLOADS_PATH, attachment_id)
+}
+
+fn get_attachment_response(state: &State, attachment_id: &u64) -> AttachmentResponse {
+    let attachment = state.attachments.get(attachment_id).unwrap();
+    AttachmentResponse {
+        attachment_id: attachment_id.to_owned(),
+        url: get_attachment_key(attachment_id.to_owned()),
+        content_type: attachment.content_type.to_owned(),
+        size: attachment.size,
+        timestamp: attachment.timestamp
+    }
+}
+
+pub fn get_post_attachments(state: &State, post_id: &u64) -> Vec<AttachmentResponse> {
+    state.relations.post_id_to_attachment_id.forward
+        .get(post_id)
+        .map(|attachment_ids| attachment_ids.keys().map(|attachment_id| get_attachment_response(state, attachment_id)).collect::<Vec<_>>())
+        .unwrap_or_default()
+}
+
+pub fn get_reply_attachments(state: &State, reply_id: &u64) -> Vec<AttachmentResponse> {
+    state.relations.reply_id_to_attachment_id.forward
+        .get(reply_id)
+        .map(|attachment_ids| attachment_ids.keys().map(|attachment_id| get_attachment_response(state, attachment_id)).collect::<Vec<_>>())
+        .unwrap_or_default()
+}
+
+// removes the attachment and its asset, pending uploads have no asset yet
+fn remove_attachment(state: &mut State, attachment_id: u64) {
+    let attachment = state.attachments.remove(&attachment_id).unwrap();
+
+    let profile_id = state.relations.profile_id_to_attachment_id.backward.get(&attachment_id).unwrap().first_key_value().map(|(profile_id, _)| profile_id.to_owned()).unwrap();
+    state.relations.profile_id_to_attachment_id.remove(profile_id, attachment_id);
+    let post_id_opt = state.relations.post_id_to_attachment_id.backward.get(&attachment_id).and_then(|x| x.first_key_value()).map(|(post_id, _)| post_id.to_owned());
+    if let Some(post_id) = post_id_opt {
+        state.relations.post_id_to_attachment_id.remove(post_id, attachment_id);
+    }
+    let reply_id_opt = state.relations.reply_id_to_attachment_id.backward.get(&attachment_id).and_then(|x| x.first_key_value()).map(|(reply_id, _)| reply_id.to_owned());
+    if let Some(reply_id) = reply_id_opt {
+        state.relations.reply_id_to_attachment_id.remove(reply_id, attachment_id);
+    }
+
+    if attachment.upload.is_none() {
+        ic_certified_assets::delete_asset(DeleteAssetArguments { key: get_attachment_key(attachment_id) });
+    }
+}
+
+pub fn remove_post_attachments(state: &mut State, post_id: u64) {
+    let attachment_ids = state.relations.post_id_to_attachment_id.forward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for attachment_id in attachment_ids {
+        remove_attachment(state, attachment_id);
+    }
+}
+
+pub fn remove_reply_attachments(state: &mut State, reply_id: u64) {
+    let attachment_ids = state.relations.reply_id_to_attachment_id.forward.get(&reply_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for attachment_id in attachment_ids {
+        remove_attachment(state, attachment_id);
+    }
+}
+
+// pending uploads expire together with their asset batch
+fn remove_expired_uploads(state: &mut State, profile_id: u64) {
+    let now = ic_cdk::api::time();
+    let attachment_ids = state.relations.profile_id_to_attachment_id.forward
+        .get(&profile_id)
+        .map(|x| x.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|attachment_id| state.attachments.get(attachment_id).unwrap().upload.as_ref().map(|upload| upload.expires_at <= now).unwrap_or(false))
+        .collect::<Vec<_>>();
+    for attachment_id in attachment_ids {
+        remove_attachment(state, attachment_id);
+    }
+}
+
+fn get_profile_attachments_size(state: &State, profile_id: &u64) -> u64 {
+    state.relations.profile_id_to_attachment_id.forward
+        .get(profile_id)
+        .map(|attachment_ids| attachment_ids.keys().map(|attachment_id| state.attachments.get(attachment_id).unwrap().size).sum())
+        .unwrap_or(0)
+}
+
+// expired uploads of other profiles are not counted, they are removed on their next upload
+fn get_total_attachments_size(state: &State) -> u64 {
+    let now = ic_cdk::api::time();
+    state.attachments
+        .values()
+        .filter(|attachment| attachment.upload.as_ref().map(|upload| upload.expires_at > now).unwrap_or(true))
+        .map(|attachment| attachment.size)
+        .sum()
+}
+
+// returns the profile id of the caller if they own the attachment
+fn get_attachment_owner_id(state: &State, caller: &Principal, attachment_id: &u64) -> Result<u64, String> {
+    let profile_id_opt = state.indexes.active_principal.get(caller);
+    if profile_id_opt.is_none() {
+        return Err("Profile does not exists".to_owned());
+    }
+    let profile_id = profile_id_opt.cloned().unwrap();
+
+    let is_owner = state.relations.profile_id_to_attachment_id.forward
+        .get(&profile_id)
+        .map(|attachment_ids| attachment_ids.contains_key(attachment_id))
+        .unwrap_or(false);
+    if !is_owner {
+        return Err("Attachment does not exist".to_owned());
+    }
+    Ok(profile_id)
+}
+
+fn is_attached(state: &State, attachment_id: &u64) -> bool {
+    state.relations.post_id_to_attachment_id.backward.contains_key(attachment_id) || state.relations.reply_id_to_attachment_id.backward.contains_key(attachment_id)
+}
+
+// starts an upload, the content is then sent in chunks and committed
+#[update]
+#[candid_method(update)]
+fn create_attachment(content_type: String, size: u64) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    if !ALLOWED_CONTENT_TYPES.contains(&content_type.as_str()) {
+        return Err("Content type is not allowed".to_owned());
+    }
+    if size == 0 || size > MAX_ATTACHMENT_SIZE {
+        return Err(format!("Attachment should be at most {} bytes", MAX_ATTACHMENT_SIZE));
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        remove_expired_uploads(&mut state, profile_id);
+        if get_profile_attachments_size(&state, &profile_id) + size > MAX_PROFILE_ATTACHMENTS_SIZE {
+            return Err("Attachments quota exceeded".to_owned());
+        }
+        if get_total_attachments_size(&state) + size > MAX_TOTAL_ATTACHMENTS_SIZE {
+            return Err("Canister attachments quota exceeded".to_owned());
+        }
+
+        let timestamp = ic_cdk::api::time();
+        let upload = AttachmentUpload {
+            batch_id: ic_certified_assets::create_batch().batch_id,
+            chunk_ids: vec![],
+            uploaded: 0,
+            expires_at: timestamp + BATCH_EXPIRY_NANOS
+        };
+        let attachment_id = uuid(&mut state);
+        state.attachments.insert(attachment_id, Attachment { content_type, size, timestamp, upload: Some(upload) });
+        state.relations.profile_id_to_attachment_id.insert(profile_id, attachment_id);
+
+        Ok(attachment_id)
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn upload_attachment_chunk(attachment_id: u64, content: ByteBuf) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        get_attachment_owner_id(&state, &caller, &attachment_id)?;
+
+        let attachment = state.attachments.get_mut(&attachment_id).unwrap();
+        let now = ic_cdk::api::time();
+        let upload = match attachment.upload.as_mut() {
+            Some(upload) if upload.expires_at > now => upload,
+            Some(_) => return Err("Upload expired".to_owned()),
+            None => return Err("Attachment is already committed".to_owned())
+        };
+        if upload.uploaded + content.len() as u64 > attachment.size {
+            return Err("Content exceeds the attachment size".to_owned());
+        }
+        if upload.uploaded == 0 && !has_content_signature(&attachment.content_type, &content) {
+            return Err("Content does not match the content type".to_owned());
+        }
+
+        upload.uploaded += content.len() as u64;
+        upload.expires_at = now + BATCH_EXPIRY_NANOS;
+        let chunk = ic_certified_assets::create_chunk(CreateChunkArg { batch_id: upload.batch_id.to_owned(), content });
+        upload.chunk_ids.push(chunk.chunk_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn commit_attachment(attachment_id: u64) -> Result<AttachmentResponse, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        get_attachment_owner_id(&state, &caller, &attachment_id)?;
+
+        let attachment = state.attachments.get_mut(&attachment_id).unwrap();
+        let upload = match attachment.upload.to_owned() {
+            Some(upload) if upload.expires_at > ic_cdk::api::time() => upload,
+            Some(_) => return Err("Upload expired".to_owned()),
+            None => return Err("Attachment is already committed".to_owned())
+        };
+        if upload.uploaded != attachment.size {
+            return Err("Attachment upload is incomplete".to_owned());
+        }
+
+        let key = get_attachment_key(attachment_id);
+        let operations = vec![
+            BatchOperation::CreateAsset(CreateAssetArguments {
+                key: key.to_owned(),
+                content_type: attachment.content_type.to_owned(),
+                max_age: None,
+                headers: None
+            }),
+            BatchOperation::SetAssetContent(SetAssetContentArguments {
+                key,
+                content_encoding: "identity".to_owned(),
+                chunk_ids: upload.chunk_ids,
+                sha256: None
+            })
+        ];
+        ic_certified_assets::commit_batch(CommitBatchArguments { batch_id: upload.batch_id, operations });
+        attachment.upload = None;
+
+        Ok(get_attachment_response(&state, &attachment_id))
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn attach_to_post(attachment_id: u64, post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id = get_attachment_owner_id(&state, &caller, &attachment_id)?;
+        if state.attachments.get(&attachment_id).unwrap().upload.is_some() {
+            return Err("Attachment is not committed".to_owned());
+        }
+        if is_attached(&state, &attachment_id) {
+            return Err("Attachment is already attached".to_owned());
+        }
+
+        let is_author = state.relations.profile_id_to_post_id.forward
+            .get(&profile_id)
+            .map(|post_ids| post_ids.contains_key(&post_id))
+            .unwrap_or(false);
+        if !is_author {
+            return Err("Caller is not the author".to_owned());
+        }
+
+        state.relations.post_id_to_attachment_id.insert(post_id, attachment_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn attach_to_reply(attachment_id: u64, reply_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id = get_attachment_owner_id(&state, &caller, &attachment_id)?;
+        if state.attachments.get(&attachment_id).unwrap().upload.is_some() {
+            return Err("Attachment is not committed".to_owned());
+        }
+        if is_attached(&state, &attachment_id) {
+            return Err("Attachment is already attached".to_owned());
+        }
+
+        let is_author = state.relations.profile_id_to_reply_id.forward
+            .get(&profile_id)
+            .map(|reply_ids| reply_ids.contains_key(&reply_id))
+            .unwrap_or(false);
+        if !is_author {
+            return Err("Caller is not the author".to_owned());
+        }
+
+        state.relations.reply_id_to_attachment_id.insert(reply_id, attachment_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn delete_attachment(attachment_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if !state.attachments.contains_key(&attachment_id) {
+            return Err("Attachment does not exist".to_owned());
+        }
+        if !caller_can_hide_content {
+            get_attachment_owner_id(&state, &caller, &attachment_id)?;
+        }
+
+        remove_attachment(&mut state, attachment_id);
+
+        Ok(())
+    })
+}
+
+// attachments of the caller with the bytes used from the quota
+#[query]
+#[candid_method(query)]
+fn get_attachments() -> Result<(Vec<AttachmentResponse>, u64), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let attachments = state.relations.profile_id_to_attachment_id.forward
+            .get(profile_id)
+            .map(|attachment_ids| attachment_ids.keys().filter(|attachment_id| state.attachments.get(attachment_id).unwrap().upload.is_none()).map(|attachment_id| get_attachment_response(&state, attachment_id)).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        Ok((attachments, get_profile_attachments_size(&state, profile_id)))
+    })
+}
+
+#[test]
+fn content_signatures() {
+    assert!(has_content_signature("image/png", b"\x89PNG\r\n\x1a\n\x00\x00"));
+    assert!(has_content_signature("image/jpeg", b"\xff\xd8\xff\xe0"));
+    assert!(has_content_signature("image/gif", b"GIF89a\x01\x00"));
+    assert!(has_content_signature("image/webp", b"RIFF\x24\x00\x00\x00WEBPVP8 "));
+    assert!(has_content_signature("application/pdf", b"%PDF-1.7"));
+    assert!(!has_content_signature("image/png", b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>"));
+    assert!(!has_content_signature("image/webp", b"RIFF\x24\x00\x00\x00WAVE"));
+    assert!(!has_content_signature("image/png", b"\x89PN"));
+}
@@ -0,0 +1,95 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+
+use crate::state::{State, STATE, PostStatus, PostSort, PostCursor, PostsPage, Permission};
+use crate::roles::caller_has_permission;
+use crate::get_page;
+
+pub fn remove_post_bookmarks(state: &mut State, post_id: u64) {
+    let profile_ids = state.relations.profile_id_to_bookmarked_post_id.backward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for profile_id in profile_ids {
+        state.relations.profile_id_to_bookmarked_post_id.remove(profile_id, post_id);
+    }
+}
+
+#[update]
+#[candid_method(update)]
+fn bookmark_post(post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let post_opt = state.posts.get(&post_id);
+        if post_opt.is_none() || post_opt.unwrap().status == PostStatus::Hidden {
+            return Err("Post does not exist".to_owned());
+        }
+
+        let is_bookmarked = state.relations.profile_id_to_bookmarked_post_id.forward
+            .get(&profile_id)
+            .map(|post_ids| post_ids.contains_key(&post_id))
+            .unwrap_or(false);
+        if is_bookmarked {
+            return Err("Post is already bookmarked".to_owned());
+        }
+
+        state.relations.profile_id_to_bookmarked_post_id.insert(profile_id, post_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn unbookmark_post(post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let is_bookmarked = state.relations.profile_id_to_bookmarked_post_id.forward
+            .get(&profile_id)
+            .map(|post_ids| post_ids.contains_key(&post_id))
+            .unwrap_or(false);
+        if !is_bookmarked {
+            return Err("Post is not bookmarked".to_owned());
+        }
+
+        state.relations.profile_id_to_bookmarked_post_id.remove(profile_id, post_id);
+
+        Ok(())
+    })
+}
+
+// bookmarked posts of the caller, hidden posts are skipped for non moderators
+#[query]
+#[candid_method(query)]
+fn get_bookmarks(sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> Result<PostsPage, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let post_ids = state.relations.profile_id_to_bookmarked_post_id.forward.get(profile_id).cloned().unwrap_or_default();
+        Ok(get_page(&state, sort, cursor_opt, limit_opt, caller_can_hide_content, |post_id| post_ids.contains_key(post_id)))
+    })
+}
@@ -15,7 +15,10 @@ use ic_certified_assets::types::StoreArg;
 use addr::parse_domain_name;
 
 use crate::STATE;
+use crate::state::State;
 use crate::upgrade::authorize;
+use crate::seo::{render_post_files, render_crawler_files};
+use crate::syndication::update_feeds;
 use crate::utils::{ get_content_type, format_number, uuid };
 
 const EXPIRE_TIME_NANOSECS: u64 = 2 * 24 * 60 * 60 * 1000 * 1000 * 1000 ; // 2 days
@@ -117,6 +120,13 @@ async fn update_registration() {
         let domain = state.domain.as_mut().unwrap();
         domain.last_status = Ok(serde_json::from_value::<DomainStatus>(registration_status["state"].clone()).unwrap());
         state.domain = Some(domain.to_owned());
+
+        // absolute urls switch to the custom domain
+        if state.domain.as_ref().unwrap().last_status == Ok(DomainStatus::Available) {
+            render_post_files(&state);
+            update_feeds(&state);
+            render_crawler_files(&state);
+        }
     });
 
 }
@@ -213,6 +223,14 @@ fn get_registration() -> Option<Domain>{
     STATE.with(|s| s.borrow().domain.clone())
 }
 
+// the custom domain once it is available, otherwise the canister domain
+pub fn get_base_url(state: &State) -> String {
+    match &state.domain {
+        Some(domain) if domain.last_status == Ok(DomainStatus::Available) => format!("https://{}", domain.domain_name),
+        _ => format!("https://{}.icp0.io", ic_cdk::id())
+    }
+}
+
 
 #[query]
 #[candid_method(query)]
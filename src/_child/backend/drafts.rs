@@ -0,0 +1,268 @@
+use candid::{candid_method, Principal};
+use ic_cdk::{update, query};
+
+use std::time::Duration;
+
+use crate::state::{State, STATE, Draft, DraftResponse, PostSummary, Permission};
+use crate::tags::normalize_tags;
+use crate::utils::uuid;
+use crate::roles::{caller_has_permission, profile_has_permission};
+use crate::{insert_post, validate_post_category};
+
+fn publish_scheduled_draft(draft_id: u64) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let is_due = state.drafts
+            .get(&draft_id)
+            .and_then(|draft| draft.publish_at)
+            .map(|publish_at| publish_at <= ic_cdk::api::time())
+            .unwrap_or(false);
+        if !is_due {
+            return;
+        }
+
+        // drafts of profiles muted or banned in the meantime stay unpublished
+        let (profile_id, _) = state.relations.profile_id_to_draft_id.backward.get(&draft_id).unwrap().first_key_value().unwrap();
+        if !profile_has_permission(&state, profile_id, Permission::CreateContent) {
+            state.drafts.get_mut(&draft_id).unwrap().publish_at = None;
+            return;
+        }
+        publish(&mut state, draft_id);
+    });
+}
+
+// timers of drafts that were rescheduled or cancelled do nothing when they fire
+fn set_draft_timer(draft_id: u64, publish_at: u64) {
+    let delay = Duration::from_nanos(publish_at.saturating_sub(ic_cdk::api::time()));
+    ic_cdk_timers::set_timer(delay, move || publish_scheduled_draft(draft_id));
+}
+
+// timers do not survive upgrades so they are set again for scheduled drafts
+pub fn set_draft_timers(state: &State) {
+    for (draft_id, draft) in state.drafts.iter() {
+        if let Some(publish_at) = draft.publish_at {
+            set_draft_timer(draft_id.to_owned(), publish_at);
+        }
+    }
+}
+
+// turns the draft into a post, the category is dropped if it was deleted in the meantime
+fn publish(state: &mut State, draft_id: u64) -> PostSummary {
+    let draft = state.drafts.remove(&draft_id).unwrap();
+    let (profile_id, _) = state.relations.profile_id_to_draft_id.backward.get(&draft_id).unwrap().first_key_value().unwrap();
+    let profile_id = profile_id.to_owned();
+    state.relations.profile_id_to_draft_id.remove(profile_id, draft_id);
+
+    let category_id_opt = draft.category_id.filter(|category_id| state.categories.contains_key(category_id));
+    insert_post(state, profile_id, draft.title, draft.description, category_id_opt, draft.tags)
+}
+
+fn get_draft_response(state: &State, draft_id: &u64) -> DraftResponse {
+    let draft = state.drafts.get(draft_id).unwrap();
+    DraftResponse {
+        draft_id: draft_id.to_owned(),
+        title: draft.title.to_owned(),
+        description: draft.description.to_owned(),
+        category_id: draft.category_id,
+        tags: draft.tags.to_owned(),
+        publish_at: draft.publish_at,
+        timestamp: draft.timestamp,
+        updated_at: draft.updated_at
+    }
+}
+
+// returns the profile id of the caller if they own the draft
+fn get_draft_owner_id(state: &State, caller: &Principal, draft_id: &u64) -> Result<u64, String> {
+    let profile_id_opt = state.indexes.active_principal.get(caller);
+    if profile_id_opt.is_none() {
+        return Err("Profile does not exists".to_owned());
+    }
+    let profile_id = profile_id_opt.cloned().unwrap();
+
+    let is_owner = state.relations.profile_id_to_draft_id.forward
+        .get(&profile_id)
+        .map(|draft_ids| draft_ids.contains_key(draft_id))
+        .unwrap_or(false);
+    if !is_owner {
+        return Err("Draft does not exist".to_owned());
+    }
+    Ok(profile_id)
+}
+
+fn get_caller_draft_ids(state: &State, caller: &Principal) -> Result<Vec<u64>, String> {
+    let profile_id_opt = state.indexes.active_principal.get(caller);
+    if profile_id_opt.is_none() {
+        return Err("Profile does not exists".to_owned());
+    }
+    let draft_ids = state.relations.profile_id_to_draft_id.forward
+        .get(profile_id_opt.unwrap())
+        .map(|draft_ids| draft_ids.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    Ok(draft_ids)
+}
+
+// creates a draft or updates it when a draft id is given
+#[update]
+#[candid_method(update)]
+fn save_draft(draft_id_opt: Option<u64>, title: String, description: String, category_id_opt: Option<u64>, tags_opt: Option<Vec<String>>) -> Result<DraftResponse, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        validate_post_category(&state, category_id_opt, caller_can_manage_categories)?;
+        let tags = normalize_tags(tags_opt.unwrap_or_default())?;
+        let timestamp = ic_cdk::api::time();
+
+        let draft_id = match draft_id_opt {
+            Some(draft_id) => {
+                get_draft_owner_id(&state, &caller, &draft_id)?;
+                let draft = state.drafts.get_mut(&draft_id).unwrap();
+                draft.title = title;
+                draft.description = description;
+                draft.category_id = category_id_opt;
+                draft.tags = tags;
+                draft.updated_at = timestamp;
+                draft_id
+            },
+            None => {
+                let draft_id = uuid(&mut state);
+                let draft = Draft {
+                    title,
+                    description,
+                    category_id: category_id_opt,
+                    tags,
+                    publish_at: None,
+                    timestamp,
+                    updated_at: timestamp
+                };
+                state.drafts.insert(draft_id, draft);
+                state.relations.profile_id_to_draft_id.insert(profile_id, draft_id);
+                draft_id
+            }
+        };
+
+        Ok(get_draft_response(&state, &draft_id))
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn delete_draft(draft_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id = get_draft_owner_id(&state, &caller, &draft_id)?;
+        state.drafts.remove(&draft_id);
+        state.relations.profile_id_to_draft_id.remove(profile_id, draft_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn publish_draft(draft_id: u64) -> Result<PostSummary, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        get_draft_owner_id(&state, &caller, &draft_id)?;
+
+        Ok(publish(&mut state, draft_id))
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn schedule_draft(draft_id: u64, publish_at: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    if publish_at <= ic_cdk::api::time() {
+        return Err("Draft should be scheduled in the future".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        get_draft_owner_id(&state, &caller, &draft_id)?;
+        state.drafts.get_mut(&draft_id).unwrap().publish_at = Some(publish_at);
+        set_draft_timer(draft_id, publish_at);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn cancel_scheduled_draft(draft_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        get_draft_owner_id(&state, &caller, &draft_id)?;
+        let draft = state.drafts.get_mut(&draft_id).unwrap();
+        if draft.publish_at.is_none() {
+            return Err("Draft is not scheduled".to_owned());
+        }
+        draft.publish_at = None;
+
+        Ok(())
+    })
+}
+
+// drafts of the caller from the most recently updated
+#[query]
+#[candid_method(query)]
+fn get_drafts() -> Result<Vec<DraftResponse>, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let mut drafts = get_caller_draft_ids(&state, &caller)?
+            .iter()
+            .map(|draft_id| get_draft_response(&state, draft_id))
+            .collect::<Vec<_>>();
+        drafts.sort_by_key(|draft| std::cmp::Reverse(draft.updated_at));
+
+        Ok(drafts)
+    })
+}
+
+// scheduled drafts of the caller from the next to be published
+#[query]
+#[candid_method(query)]
+fn get_scheduled_drafts() -> Result<Vec<DraftResponse>, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let mut drafts = get_caller_draft_ids(&state, &caller)?
+            .iter()
+            .map(|draft_id| get_draft_response(&state, draft_id))
+            .filter(|draft| draft.publish_at.is_some())
+            .collect::<Vec<_>>();
+        drafts.sort_by_key(|draft| draft.publish_at);
+
+        Ok(drafts)
+    })
+}
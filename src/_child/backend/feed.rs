@@ -0,0 +1,161 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+
+use crate::state::{State, STATE, AuthenticationWithAddress, PostStatus, PostSort, PostCursor, PostsPage, Permission};
+use crate::roles::caller_has_permission;
+use crate::get_page;
+
+pub fn get_followers_count(state: &State, profile_id: &u64) -> u64 {
+    state.relations.profile_id_to_followed_profile_id.backward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64
+}
+
+pub fn get_following_count(state: &State, profile_id: &u64) -> u64 {
+    state.relations.profile_id_to_followed_profile_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64
+}
+
+pub fn remove_post_subscriptions(state: &mut State, post_id: u64) {
+    let profile_ids = state.relations.profile_id_to_subscribed_post_id.backward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for profile_id in profile_ids {
+        state.relations.profile_id_to_subscribed_post_id.remove(profile_id, post_id);
+    }
+}
+
+#[update]
+#[candid_method(update)]
+fn follow_profile(authentication: AuthenticationWithAddress) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let followed_profile_id_opt = state.indexes.profile.get(&authentication);
+        if followed_profile_id_opt.is_none() {
+            return Err("Profile to follow does not exist".to_owned());
+        }
+        let followed_profile_id = followed_profile_id_opt.cloned().unwrap();
+        if followed_profile_id == profile_id {
+            return Err("Cannot follow yourself".to_owned());
+        }
+
+        state.relations.profile_id_to_followed_profile_id.insert(profile_id, followed_profile_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn unfollow_profile(authentication: AuthenticationWithAddress) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let followed_profile_id_opt = state.indexes.profile.get(&authentication).cloned();
+        let is_following = followed_profile_id_opt
+            .and_then(|followed_profile_id| state.relations.profile_id_to_followed_profile_id.forward.get(&profile_id).map(|x| x.contains_key(&followed_profile_id)))
+            .unwrap_or(false);
+        if !is_following {
+            return Err("Profile is not followed".to_owned());
+        }
+
+        state.relations.profile_id_to_followed_profile_id.remove(profile_id, followed_profile_id_opt.unwrap());
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn subscribe_post(post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let post_opt = state.posts.get(&post_id);
+        if post_opt.is_none() || post_opt.unwrap().status == PostStatus::Hidden {
+            return Err("Post does not exist".to_owned());
+        }
+
+        state.relations.profile_id_to_subscribed_post_id.insert(profile_id, post_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn unsubscribe_post(post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let is_subscribed = state.relations.profile_id_to_subscribed_post_id.forward
+            .get(&profile_id)
+            .map(|post_ids| post_ids.contains_key(&post_id))
+            .unwrap_or(false);
+        if !is_subscribed {
+            return Err("Post is not subscribed".to_owned());
+        }
+
+        state.relations.profile_id_to_subscribed_post_id.remove(profile_id, post_id);
+
+        Ok(())
+    })
+}
+
+// posts of followed profiles and subscribed posts ordered by their last activity
+#[query]
+#[candid_method(query)]
+fn get_feed(cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> Result<PostsPage, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let followed_profile_ids = state.relations.profile_id_to_followed_profile_id.forward.get(profile_id).cloned().unwrap_or_default();
+        let subscribed_post_ids = state.relations.profile_id_to_subscribed_post_id.forward.get(profile_id).cloned().unwrap_or_default();
+        let is_in_feed = |post_id: &u64| {
+            if subscribed_post_ids.contains_key(post_id) {
+                return true;
+            }
+            let (author_id, _) = state.relations.profile_id_to_post_id.backward.get(post_id).unwrap().first_key_value().unwrap();
+            followed_profile_ids.contains_key(author_id)
+        };
+
+        Ok(get_page(&state, PostSort::LastActivity, cursor_opt, limit_opt, caller_can_hide_content, is_in_feed))
+    })
+}
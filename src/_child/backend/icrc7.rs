@@ -80,6 +80,22 @@ pub struct Standard {
     pub url: String,
 }
 
+// only admin roles are tokens, the other roles are assigned by admins and cannot be transferred
+fn is_token(state: &State, role_id: &u64) -> bool {
+    state.roles.get(role_id).map(|role| role.role == UserRole::Admin).unwrap_or(false)
+}
+
+fn get_token_ids(state: &State) -> Vec<u64> {
+    state.roles.keys().filter(|role_id| is_token(state, role_id)).cloned().collect()
+}
+
+fn get_profile_token_ids(state: &State, profile_id: &u64) -> Vec<u64> {
+    state.relations.profile_id_to_role_id.forward
+        .get(profile_id)
+        .map(|role_ids| role_ids.keys().filter(|role_id| is_token(state, role_id)).cloned().collect())
+        .unwrap_or_default()
+}
+
 #[query]
 pub fn icrc7_symbol() -> String {
    format!("COM-{}", &ic_cdk::id().to_text()[0..5])
@@ -101,7 +117,7 @@ pub fn icrc7_logo() -> Option<String> {
 
 #[query]
 pub fn icrc7_total_supply() -> u128 {
-    STATE.with(|s| s.borrow().roles.len() as u128)
+    STATE.with(|s| get_token_ids(&s.borrow()).len() as u128)
 }
 
 #[query]
@@ -145,7 +161,7 @@ pub fn icrc7_owner_of(ids: Vec<u128>) -> Vec<Option<Account>> {
         let state = s.borrow();
         ids.iter().map(|id| {
             let profile_id_opt = state.relations.profile_id_to_role_id.backward.get(&(*id as u64));
-            if profile_id_opt.is_none() {
+            if profile_id_opt.is_none() || !is_token(&state, &(*id as u64)) {
                 return None; 
             }
             let (profile_id, _) = profile_id_opt.unwrap().first_key_value().unwrap();
@@ -173,12 +189,13 @@ pub fn icrc7_tokens(prev: Option<u128>, take: Option<u128>) -> Vec<u128> {
             ic_cdk::trap("Exceeds Max Take Value")
         }
 
+        let token_ids = get_token_ids(&state);
         match prev {
-            Some(prev) => match state.roles.iter().position(|(id, _)| *id == prev as u64) {
+            Some(prev) => match token_ids.iter().position(|id| *id == prev as u64) {
                 None => vec![],
-                Some(index) => state.roles.iter().map(|(id, _)| *id as u128).skip(index).take(take as usize).collect(),
+                Some(index) => token_ids.iter().map(|id| *id as u128).skip(index).take(take as usize).collect(),
             },
-            None => state.roles.iter().map(|(id, _)| *id as u128).take(take as usize).collect::<Vec<_>>(),
+            None => token_ids.iter().map(|id| *id as u128).take(take as usize).collect::<Vec<_>>(),
         }
     })
 }
@@ -191,8 +208,7 @@ pub fn icrc7_token_metadata(token_ids: Vec<u128>) -> Vec<Option<HashMap<String,
             ic_cdk::trap("Exceeds Max Query Batch Size")
         }
         token_ids.iter().map(|token_id| {
-            let role_opt =  state.roles.get(&(*token_id as u64));
-            if role_opt.is_some() {
+            if is_token(&state, &(*token_id as u64)) {
                 let mut metadata: HashMap<String, MetadataValue> = HashMap::new();
                 metadata.insert("Name".into(), MetadataValue::Text(format!("Token {token_id}")));
                 metadata.insert("Symbol".into(), MetadataValue::Text(icrc7_symbol()));
@@ -215,14 +231,7 @@ pub fn icrc7_balance_of(accounts: Vec<Account>) -> Vec<u128> {
                 return 0;
             }
 
-            // is not a admin 
-            let profile_id = profile_id_opt.unwrap();
-            let roles_ids_opt = state.relations.profile_id_to_role_id.forward.get(profile_id);
-            if roles_ids_opt.is_none() {
-                return 0;
-            }
-
-            roles_ids_opt.unwrap().len() as u128
+            get_profile_token_ids(&state, profile_id_opt.unwrap()).len() as u128
         }).collect::<Vec<_>>()
     })
 }
@@ -242,13 +251,11 @@ pub fn icrc7_tokens_of(account: Account, prev: Option<u128>, take: Option<u128>)
         }
 
         // is not a admin 
-        let profile_id = profile_id_opt.unwrap();
-        let role_id_opt = state.relations.profile_id_to_role_id.forward.get(profile_id);
-        if role_id_opt.is_none() {
+        let role_ids = get_profile_token_ids(&state, profile_id_opt.unwrap()).iter().map(|id| *id as u128).collect::<Vec<_>>();
+        if role_ids.is_empty() {
             return vec![];
         }
 
-        let role_ids = role_id_opt.unwrap().iter().map(|(id, _)| *id as u128).collect::<Vec<_>>();
         match prev {
             Some(prev) => match role_ids.iter().position(|id| *id == prev ) {
                 None => vec![],
@@ -282,8 +289,7 @@ async fn mint(arg: MintArg) -> Result<u128, MintError> {
         
         // check if caller has ntf
         let profile_id_caller = state.indexes.active_principal.get(&caller).unwrap();
-        let role_id_caller_opt = state.relations.profile_id_to_role_id.forward.get(profile_id_caller);
-        if role_id_caller_opt.is_none() {
+        if get_profile_token_ids(&state, profile_id_caller).is_empty() {
             return Err(MintError::Unauthorized);
         }
 
@@ -292,8 +298,7 @@ async fn mint(arg: MintArg) -> Result<u128, MintError> {
         let profile_id_to = if let Some(profile_id) = profile_id_to_opt {
 
             // check the to has already nft
-            let role_id_to_opt = state.relations.profile_id_to_role_id.forward.get(&profile_id);
-            if role_id_to_opt.is_some() {
+            if !get_profile_token_ids(&state, profile_id).is_empty() {
                 return Err(MintError::GenericError { error_code: 1, message: "Principal already has token".to_owned() });
             }
 
@@ -395,7 +400,7 @@ fn transfer_check(state: &RefMut<'_, State>, current_time: &u64, caller: &Accoun
         txn_deduplication_check(state,&allowed_past_time, caller, arg)?;
     }
     // checking is token for the corresponding ID exists or not
-    if state.roles.get(&(arg.token_id as u64)).is_none() {
+    if !is_token(state, &(arg.token_id as u64)) {
         return Err(TransferError::NonExistingTokenId);
     }
 
@@ -414,7 +419,7 @@ fn transfer_check(state: &RefMut<'_, State>, current_time: &u64, caller: &Accoun
     // checking if the to account has already
     let to_profile_id_opt = state.indexes.active_principal.get(&arg.to.owner);
     if let Some(to_profile_id) = to_profile_id_opt {
-        if state.relations.profile_id_to_role_id.forward.contains_key(to_profile_id) {
+        if !get_profile_token_ids(state, to_profile_id).is_empty() {
             return Err(TransferError::GenericBatchError { error_code: 1, message: "Principal already has token".to_owned() });
         }
     }
@@ -541,7 +546,7 @@ fn burn_check(state: &RefMut<'_, State>,caller: &Account, arg: &BurnArg) -> Resu
         }
     }
 
-    if !state.roles.contains_key(&(arg.token_id as u64)) {
+    if !is_token(state, &(arg.token_id as u64)) {
         return Err(BurnError::NonExistingTokenId);
     }
 
@@ -651,8 +656,8 @@ async fn reset_tokens() -> Result<(), String> {
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         let caller_account = default_account(&caller);
-        let roles = state.roles.clone();
-        for (token_id, _) in roles {
+        let token_ids = get_token_ids(&state);
+        for token_id in token_ids.iter() {
             log_transaction(
                 &mut state,
                 TransactionType::Burn {
@@ -665,8 +670,13 @@ async fn reset_tokens() -> Result<(), String> {
             );
         }
 
-        state.roles.clear();
-        state.relations.profile_id_to_role_id.clear();
+        // the other roles are kept
+        for token_id in token_ids {
+            let (profile_id, _) = state.relations.profile_id_to_role_id.backward.get(&token_id).unwrap().first_key_value().unwrap();
+            let profile_id = profile_id.to_owned();
+            state.roles.remove(&token_id);
+            state.relations.profile_id_to_role_id.remove(profile_id, token_id);
+        }
 
         for controller in canister_status.settings.controllers.iter() {
             if controller == &ic_cdk::id() { continue; }
@@ -6,8 +6,24 @@ mod auth;
 mod icrc7;
 mod icrc3;
 mod domain;
-
-use std::collections::BTreeSet;
+mod names;
+mod search;
+mod tags;
+mod polls;
+mod reactions;
+mod messages;
+mod notifications;
+mod mentions;
+mod markdown;
+mod attachments;
+mod bookmarks;
+mod feed;
+mod drafts;
+mod seo;
+mod syndication;
+mod api;
+mod roles;
+mod sanctions;
 
 use candid::{ CandidType, Deserialize, Principal, candid_method};
 use ic_cdk::api::management_canister::main::CanisterStatusResponse;
@@ -20,10 +36,31 @@ use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
 use crate::state::{*, STATE};
 use upgrade::{update_metadata, check_canister_cycles_balance, replace_assets_from_temp, authorize, store_assets_to_temp, upgrade_canister_cb};
 use upgrade::UpgradeWithTrack;
-use utils::{uuid, get_asset, get_user_roles, default_account };
+use utils::{uuid, get_asset, default_account, migrate_in_batches };
 use auth::{get_authentication_with_address, login_message_hex_svm, login_message_hex_evm};
+use search::{index_post, unindex_post, index_reply, unindex_reply, migrate_index};
+use tags::{normalize_tags, get_post_tags, index_post_tags, unindex_post_tags, migrate_tag_index};
+use polls::{get_poll_response, set_poll_timers};
+use drafts::set_draft_timers;
+use notifications::notify;
+use mentions::{update_post_mentions, update_reply_mentions, get_post_mentions, get_reply_mentions};
+use markdown::{update_post_html, update_reply_html, get_post_html, get_reply_html, render_missing_html};
+use attachments::{get_post_attachments, get_reply_attachments, remove_post_attachments, remove_reply_attachments};
+use bookmarks::remove_post_bookmarks;
+use seo::{update_post_page, render_post_files, update_sitemap, render_crawler_files};
+use syndication::{update_feeds, update_post_feed};
+use api::{is_api_request, is_preflight_request, is_uncertified_request, handle_api_request, build_upgrade};
+use feed::{get_followers_count, get_following_count, remove_post_subscriptions};
+use reactions::{get_reaction_id, add_post_reaction, add_reply_reaction, remove_reaction, get_post_likes, get_reply_likes, get_post_likes_count, get_profile_likes_count, get_post_reactions, get_reply_reactions, migrate_likes};
+use roles::{caller_has_permission, get_profile_roles};
+use names::{address_to_name, get_address, handle_key, validate_handle, validate_description};
 use candid::{Encode, Decode};
 
+const DEFAULT_MAX_REPLY_DEPTH: u64 = 5;
+const DEFAULT_PAGE_LIMIT: u64 = 20;
+const MAX_PAGE_LIMIT: u64 = 100;
+const MAX_PINNED_POSTS: usize = 20;
+
 #[init]
 #[candid_method(init)]
 fn init(admin_opt: Option<Principal>, version_opt: Option<String>, track_opt: Option<String>) {
@@ -48,7 +85,8 @@ fn create_profile_by_principal(principal: &Principal) -> u64 {
         let mut state = s.borrow_mut();
         let authentication = Authentication::Ic;
         let profile_id  = uuid(&mut state);
-        let profile = Profile { name:"".to_owned(), description: "".to_owned(), authentication, active_principal: principal.to_owned(), timestamp: ic_cdk::api::time(), last_login: ic_cdk::api::time() };
+        let name = address_to_name(&principal.to_string());
+        let profile = Profile { name, description: "".to_owned(), authentication, active_principal: principal.to_owned(), timestamp: ic_cdk::api::time(), last_login: ic_cdk::api::time() };
         state.profiles.insert(profile_id.to_owned(), profile);
         state.indexes.active_principal.insert(principal.to_owned(), profile_id);
         state.indexes.profile.insert(AuthenticationWithAddress::Ic(IcParams { principal: principal.to_owned() }), profile_id);
@@ -131,7 +169,7 @@ fn create_profile(auth: AuthenticationWith) -> Result<Profile, String> {
 
         let profile = Profile {
             authentication: authentication_profile,
-            name: "".to_owned(),
+            name: address_to_name(&get_address(&authentication_with_address)),
             description: "".to_owned(),
             active_principal: caller,
             last_login: ic_cdk::api::time(),
@@ -145,56 +183,161 @@ fn create_profile(auth: AuthenticationWith) -> Result<Profile, String> {
 
 #[update]
 #[candid_method(update)]
-fn create_post(title: String, description: String) -> Result<PostSummary, String> {
+fn update_profile(name_opt: Option<String>, description_opt: Option<String>) -> Result<Profile, String> {
     let caller = ic_cdk::caller();
 
     STATE.with(|s| {
         let mut state = s.borrow_mut();
 
         let profile_id_opt = state.indexes.active_principal.get(&caller);
-
         if profile_id_opt == None {
             return Err("Profile does not exists".to_owned());
         }
         let profile_id = profile_id_opt.cloned().unwrap();
+        let mut profile = state.profiles.get(&profile_id).cloned().unwrap();
 
-        let post_id = uuid(&mut state);
+        if let Some(description) = &description_opt {
+            validate_description(description)?;
+        }
 
-        let post = Post {
-            title,
-            description,
-            timestamp: ic_cdk::api::time(),
-            status: PostStatus::Visible
-        };
+        if let Some(name) = name_opt {
+            let is_handle = !name.is_empty();
+            if is_handle {
+                validate_handle(&name)?;
+                let handle_profile_id_opt = state.indexes.handle.get(&handle_key(&name));
+                if handle_profile_id_opt.is_some() && handle_profile_id_opt != Some(&profile_id) {
+                    return Err("Name is already taken".to_owned());
+                }
+            }
 
-        state.posts.insert(post_id, post.clone());
+            let prev_handle = handle_key(&profile.name);
+            if state.indexes.handle.get(&prev_handle) == Some(&profile_id) {
+                state.indexes.handle.remove(&prev_handle);
+            }
 
-        state.relations.profile_id_to_post_id.insert(profile_id, post_id);
+            // empty name resets to the default name
+            if is_handle {
+                state.indexes.handle.insert(handle_key(&name), profile_id);
+                profile.name = name;
+            } else {
+                let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+                profile.name = address_to_name(&get_address(&authentication));
+            }
+        }
 
-        let profile = state.profiles.get(&profile_id).unwrap();
+        if let Some(description) = description_opt {
+            profile.description = description;
+        }
 
-        let authentication = get_authentication_with_address( &profile.authentication, &profile.active_principal);
-        let post = PostSummary {
-            title: post.title,
-            post_id,
-            description: post.description,
-            timestamp: post.timestamp,
-            replies_count: 0,
-            last_activity: post.timestamp,
-            authentication,
-            status: post.status
-        };
-        Ok(post)
+        state.profiles.insert(profile_id, profile.clone());
+        Ok(profile)
     })
 }
 
 #[update]
 #[candid_method(update)]
-fn create_reply(post_id: u64, context: String) -> Result<ReplyResponse, String> {
+fn create_post(title: String, description: String, category_id_opt: Option<u64>, tags_opt: Option<Vec<String>>) -> Result<PostSummary, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
     STATE.with(|s| {
         let mut state = s.borrow_mut();
 
-        let caller = ic_cdk::caller();
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+
+        if profile_id_opt == None {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        validate_post_category(&state, category_id_opt, caller_can_manage_categories)?;
+        let tags = normalize_tags(tags_opt.unwrap_or_default())?;
+
+        Ok(insert_post(&mut state, profile_id, title, description, category_id_opt, tags))
+    })
+}
+
+fn validate_post_category(state: &State, category_id_opt: Option<u64>, caller_can_manage_categories: bool) -> Result<(), String> {
+    if let Some(category_id) = category_id_opt {
+        let category_opt = state.categories.get(&category_id);
+        if category_opt.is_none() {
+            return Err("Category does not exist".to_owned());
+        }
+        if category_opt.unwrap().admin_only && !caller_can_manage_categories {
+            return Err("Category is admin only".to_owned());
+        }
+    }
+    Ok(())
+}
+
+// stores a new post of the profile, the category and the tags should be already validated
+fn insert_post(state: &mut State, profile_id: u64, title: String, description: String, category_id_opt: Option<u64>, tags: Vec<String>) -> PostSummary {
+    let post_id = uuid(state);
+
+    let post = Post {
+        title,
+        description,
+        timestamp: ic_cdk::api::time(),
+        status: PostStatus::Visible,
+        edited_at: None,
+        tags: Some(tags.to_owned()),
+        mentions: None,
+        description_html: None,
+        flags: Some(PostFlags::default())
+    };
+
+    state.posts.insert(post_id, post.clone());
+    update_post_html(state, post_id);
+
+    state.relations.profile_id_to_post_id.insert(profile_id, post_id);
+    if let Some(category_id) = category_id_opt {
+        state.relations.category_id_to_post_id.insert(category_id, post_id);
+    }
+
+    let post_stats = PostStats { replies_count: 0, likes_count: 0, last_activity: 0 };
+    state.post_stats.insert(post_id, post_stats);
+
+    index_post(state, post_id);
+    index_post_tags(state, post_id);
+    update_post_indexes(state, post_id);
+    update_post_mentions(state, post_id, profile_id);
+    update_post_page(state, post_id);
+    update_post_feed(state, post_id);
+    update_feeds(state);
+    update_sitemap();
+
+    let profile = state.profiles.get(&profile_id).unwrap();
+
+    let authentication = get_authentication_with_address( &profile.authentication, &profile.active_principal);
+    PostSummary {
+        title: post.title,
+        post_id,
+        description: post.description,
+        timestamp: post.timestamp,
+        replies_count: 0,
+        likes_count: 0,
+        last_activity: post.timestamp,
+        authentication,
+        status: post.status,
+        category_id: category_id_opt,
+        tags,
+        flags: PostFlags::default()
+    }
+}
+
+#[update]
+#[candid_method(update)]
+fn create_reply(post_id: u64, context: String, parent_reply_id_opt: Option<u64>) -> Result<ReplyResponse, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
 
         if !state.indexes.active_principal.contains_key(&caller){
             return Err("Profile does not exist".to_owned());
@@ -203,11 +346,31 @@ fn create_reply(post_id: u64, context: String) -> Result<ReplyResponse, String>
         if !state.posts.contains_key(&post_id) {
             return Err("Post does not exist".to_owned());
         }
+        if get_post_flags(&state, &post_id).locked {
+            return Err("Post is locked".to_owned());
+        }
+
+        let depth = if let Some(parent_reply_id) = parent_reply_id_opt {
+            let parent_post_ids_opt = state.relations.reply_id_to_post_id.forward.get(&parent_reply_id);
+            if parent_post_ids_opt.is_none() || !parent_post_ids_opt.unwrap().contains_key(&post_id) {
+                return Err("Parent reply does not exist".to_owned());
+            }
+            let depth = get_reply_depth(&state, &parent_reply_id) + 1;
+            if depth > state.max_reply_depth.unwrap_or(DEFAULT_MAX_REPLY_DEPTH) {
+                return Err("Maximum reply depth reached".to_owned());
+            }
+            depth
+        } else {
+            0
+        };
 
         let reply = Reply {
             text: context.to_owned(),
             timestamp: ic_cdk::api::time(),
-            status: ReplyStatus::Visible
+            status: ReplyStatus::Visible,
+            edited_at: None,
+            mentions: None,
+            text_html: None
         };
 
         let profile_id = state.indexes.active_principal.get(&caller).cloned().unwrap();
@@ -215,11 +378,39 @@ fn create_reply(post_id: u64, context: String) -> Result<ReplyResponse, String>
         let reply_id = uuid(&mut state);
 
         state.replies.insert(reply_id, reply.clone());
+        update_reply_html(&mut state, reply_id);
 
         state.relations.profile_id_to_reply_id.insert(profile_id.clone(), reply_id.clone());
 
         state.relations.reply_id_to_post_id.insert(reply_id.clone(), post_id.clone());
 
+        if let Some(parent_reply_id) = parent_reply_id_opt {
+            state.relations.reply_id_to_parent_reply_id.insert(reply_id, parent_reply_id);
+        }
+
+        let post_stats = state.post_stats.get_mut(&post_id).unwrap();
+        post_stats.replies_count += 1;
+        post_stats.last_activity = reply.timestamp;
+
+        index_reply(&mut state, reply_id);
+        update_post_indexes(&mut state, post_id);
+        update_reply_mentions(&mut state, reply_id, profile_id);
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+        update_sitemap();
+
+        // notify the post author and the author of the replied reply
+        let (post_author_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+        let post_author_id = post_author_id.to_owned();
+        notify(&mut state, post_author_id, NotificationKind::Reply, profile_id, post_id, Some(reply_id));
+        if let Some(parent_reply_id) = parent_reply_id_opt {
+            let (parent_author_id, _) = state.relations.profile_id_to_reply_id.backward.get(&parent_reply_id).unwrap().first_key_value().unwrap();
+            let parent_author_id = parent_author_id.to_owned();
+            if parent_author_id != post_author_id {
+                notify(&mut state, parent_author_id, NotificationKind::Reply, profile_id, post_id, Some(reply_id));
+            }
+        }
+
         let profile = state.profiles.get(&profile_id).unwrap();
         let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
 
@@ -229,25 +420,75 @@ fn create_reply(post_id: u64, context: String) -> Result<ReplyResponse, String>
             authentication,
             reply_id: reply_id,
             status: reply.status,
-            likes: vec![]
+            likes: vec![],
+            edited_at: reply.edited_at,
+            parent_reply_id: parent_reply_id_opt,
+            depth,
+            reactions: vec![],
+            mentions: get_reply_mentions(&state, &reply_id),
+            text_html: get_reply_html(&state, &reply_id),
+            attachments: vec![]
         };
 
         Ok(reply_response)
     })
 }
+
+fn get_parent_reply_id(state: &State, reply_id: &u64) -> Option<u64> {
+    state.relations.reply_id_to_parent_reply_id.forward.get(reply_id).map(|parent_ids| parent_ids.first_key_value().unwrap().0.to_owned())
+}
+
+fn get_reply_depth(state: &State, reply_id: &u64) -> u64 {
+    let mut depth = 0;
+    let mut current_reply_id = reply_id.to_owned();
+    while let Some(parent_reply_id) = get_parent_reply_id(state, &current_reply_id) {
+        current_reply_id = parent_reply_id;
+        depth += 1;
+    }
+    depth
+}
+
+// replies of a post in thread order (depth first, oldest first) with their depth
+fn get_thread_reply_ids(state: &State, post_id: &u64) -> Vec<(u64, u64)> {
+    let reply_ids_opt = state.relations.reply_id_to_post_id.backward.get(post_id);
+    if reply_ids_opt.is_none() {
+        return vec![];
+    }
+
+    let sorted_by_timestamp = |reply_ids: Vec<u64>| {
+        let mut reply_ids = reply_ids;
+        reply_ids.sort_by_key(|reply_id| state.replies.get(reply_id).unwrap().timestamp);
+        reply_ids
+    };
+
+    let root_reply_ids = reply_ids_opt
+        .unwrap()
+        .keys()
+        .filter(|reply_id| get_parent_reply_id(state, reply_id).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut stack = sorted_by_timestamp(root_reply_ids).into_iter().rev().map(|reply_id| (reply_id, 0)).collect::<Vec<_>>();
+    let mut result = vec![];
+    while let Some((reply_id, depth)) = stack.pop() {
+        result.push((reply_id, depth));
+        if let Some(child_reply_ids) = state.relations.reply_id_to_parent_reply_id.backward.get(&reply_id) {
+            let child_reply_ids = sorted_by_timestamp(child_reply_ids.keys().cloned().collect());
+            stack.extend(child_reply_ids.into_iter().rev().map(|child_reply_id| (child_reply_id, depth + 1)));
+        }
+    }
+    result
+}
+
 #[update]
 #[candid_method(update)]
 fn update_post_status(post_id: u64, status: PostStatus) -> Result<(), String> {
 
     let caller = ic_cdk::caller();
-    let caller_roles_opt = get_user_roles(&caller);
-    let caller_is_admin = match caller_roles_opt {
-        Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-        None => false
-    };
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
-    if !caller_is_admin {
-        return Err("Caller is not admin".to_owned())
+    if !caller_can_hide_content {
+        return Err("Caller does not have permission".to_owned())
     }
 
     STATE.with(|s| {
@@ -260,6 +501,45 @@ fn update_post_status(post_id: u64, status: PostStatus) -> Result<(), String> {
         let post  = post_opt.unwrap();
         post.status = status;
 
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+        update_feeds(&state);
+        update_sitemap();
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn update_post_flags(post_id: u64, flags: PostFlags) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_pin_posts = caller_has_permission(&caller, Permission::PinPosts);
+
+    if !caller_can_pin_posts {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let post_opt = state.posts.get(&post_id);
+        if post_opt.is_none() {
+            return Err("Post does not exist".to_owned());
+        }
+
+        // pinned posts are all listed on the first page so they are limited
+        let is_pinned = state.indexes.pinned_posts.contains(&post_id);
+        if flags.pinned && !is_pinned && state.indexes.pinned_posts.len() >= MAX_PINNED_POSTS {
+            return Err(format!("Only {} posts can be pinned", MAX_PINNED_POSTS));
+        }
+        if flags.pinned {
+            state.indexes.pinned_posts.insert(post_id);
+        } else {
+            state.indexes.pinned_posts.remove(&post_id);
+        }
+        state.posts.get_mut(&post_id).unwrap().flags = Some(flags);
+
         Ok(())
     })
 }
@@ -267,14 +547,10 @@ fn update_post_status(post_id: u64, status: PostStatus) -> Result<(), String> {
 #[candid_method(update)]
 fn update_reply_status(reply_id: u64, status: ReplyStatus) -> Result<(), String> {
     let caller = ic_cdk::caller();
-    let caller_roles_opt = get_user_roles(&caller);
-    let caller_is_admin = match caller_roles_opt {
-        Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-        None => false
-    };
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
-    if !caller_is_admin {
-        return Err("Caller is not admin".to_owned())
+    if !caller_can_hide_content {
+        return Err("Caller does not have permission".to_owned())
     }
 
     STATE.with(|s| {
@@ -284,12 +560,411 @@ fn update_reply_status(reply_id: u64, status: ReplyStatus) -> Result<(), String>
             return Err("Post does not exist".to_owned());
         }
         let reply = reply_opt.unwrap();
-        reply.status = status;
+        let prev_status = reply.status.to_owned();
+        reply.status = status.to_owned();
+
+        let post_id = state.relations.reply_id_to_post_id.forward.get(&reply_id).unwrap().first_key_value().unwrap().0.to_owned();
+        let post_stats = state.post_stats.get_mut(&post_id).unwrap();
+        if prev_status == ReplyStatus::Visible && status == ReplyStatus::Hidden {
+            post_stats.replies_count -= 1;
+        } else if prev_status == ReplyStatus::Hidden && status == ReplyStatus::Visible {
+            post_stats.replies_count += 1;
+        }
+        update_post_indexes(&mut state, post_id);
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+        update_sitemap();
+
+        Ok(())
+    })
+}
+
+fn purge_reply(state: &mut State, reply_id: u64) {
+    let reaction_ids = state.relations.reply_id_to_reaction_id.forward.get(&reply_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for reaction_id in reaction_ids {
+        remove_reaction(state, reaction_id);
+    }
+    remove_reply_attachments(state, reply_id);
+
+    let revision_ids = state.relations.reply_id_to_reply_revision_id.forward.get(&reply_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for revision_id in revision_ids {
+        state.reply_revisions.remove(&revision_id);
+        state.relations.reply_id_to_reply_revision_id.remove(reply_id, revision_id);
+    }
+    let mentioned_profile_ids = state.relations.reply_id_to_mentioned_profile_id.forward.get(&reply_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for mentioned_profile_id in mentioned_profile_ids {
+        state.relations.reply_id_to_mentioned_profile_id.remove(reply_id, mentioned_profile_id);
+    }
+
+    unindex_reply(state, reply_id);
+    let parent_reply_id_opt = state.relations.reply_id_to_parent_reply_id.forward.get(&reply_id).and_then(|x| x.first_key_value()).map(|(parent_reply_id, _)| parent_reply_id.to_owned());
+    if let Some(parent_reply_id) = parent_reply_id_opt {
+        state.relations.reply_id_to_parent_reply_id.remove(reply_id, parent_reply_id);
+    }
+    let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(&reply_id).unwrap().first_key_value().unwrap();
+    state.relations.reply_id_to_post_id.remove(reply_id, post_id.to_owned());
+    let (profile_id, _) = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().first_key_value().unwrap();
+    state.relations.profile_id_to_reply_id.remove(profile_id.to_owned(), reply_id);
+    state.replies.remove(&reply_id);
+}
+
+// permanently removes a post with its replies, reactions, revisions and attachments
+#[update]
+#[candid_method(update)]
+fn purge_post(post_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_community = caller_has_permission(&caller, Permission::ManageCommunity);
+
+    if !caller_can_manage_community {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if !state.posts.contains_key(&post_id) {
+            return Err("Post does not exist".to_owned());
+        }
+
+        let reply_ids = state.relations.reply_id_to_post_id.backward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        for reply_id in reply_ids {
+            purge_reply(&mut state, reply_id);
+        }
+
+        let reaction_ids = state.relations.post_id_to_reaction_id.forward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        for reaction_id in reaction_ids {
+            remove_reaction(&mut state, reaction_id);
+        }
+        remove_post_attachments(&mut state, post_id);
+        remove_post_bookmarks(&mut state, post_id);
+        remove_post_subscriptions(&mut state, post_id);
+
+        let revision_ids = state.relations.post_id_to_post_revision_id.forward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        for revision_id in revision_ids {
+            state.post_revisions.remove(&revision_id);
+            state.relations.post_id_to_post_revision_id.remove(post_id, revision_id);
+        }
+        let mentioned_profile_ids = state.relations.post_id_to_mentioned_profile_id.forward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        for mentioned_profile_id in mentioned_profile_ids {
+            state.relations.post_id_to_mentioned_profile_id.remove(post_id, mentioned_profile_id);
+        }
+        if let Some(category_id) = get_post_category_id(&state, &post_id) {
+            state.relations.category_id_to_post_id.remove(category_id, post_id);
+        }
+
+        unindex_post(&mut state, post_id);
+        unindex_post_tags(&mut state, post_id);
+        state.indexes.posts_by_timestamp.remove(post_id);
+        state.indexes.posts_by_last_activity.remove(post_id);
+        state.indexes.posts_by_likes.remove(post_id);
+        state.indexes.posts_by_replies.remove(post_id);
+        state.indexes.pinned_posts.remove(&post_id);
+
+        let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+        let profile_id = profile_id.to_owned();
+        state.relations.profile_id_to_post_id.remove(profile_id, post_id);
+        state.posts.remove(&post_id);
+        state.post_stats.remove(&post_id);
+        state.polls.remove(&post_id);
+        for inbox in state.inboxes.values_mut() {
+            inbox.retain(|notification| notification.post_id != post_id);
+        }
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+        update_feeds(&state);
+        update_sitemap();
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn edit_post(post_id: u64, title: String, description: String, tags_opt: Option<Vec<String>>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let post_opt = state.posts.get(&post_id);
+        if post_opt.is_none() {
+            return Err("Post does not exist".to_owned());
+        }
+        let post = post_opt.cloned().unwrap();
+
+        let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+        if !caller_can_hide_content && state.indexes.active_principal.get(&caller) != Some(profile_id) {
+            return Err("Caller is not the author".to_owned());
+        }
+        let profile_id = profile_id.to_owned();
+
+        let tags_opt = match tags_opt {
+            Some(tags) => Some(normalize_tags(tags)?),
+            None => None
+        };
+
+        // store previous version
+        let revision_id = uuid(&mut state);
+        let revision = PostRevision {
+            title: post.title.to_owned(),
+            description: post.description.to_owned(),
+            timestamp: post.edited_at.unwrap_or(post.timestamp)
+        };
+        state.post_revisions.insert(revision_id, revision);
+        state.relations.post_id_to_post_revision_id.insert(post_id, revision_id);
+
+        unindex_post(&mut state, post_id);
+        unindex_post_tags(&mut state, post_id);
+        let post = state.posts.get_mut(&post_id).unwrap();
+        post.title = title;
+        post.description = description;
+        if tags_opt.is_some() {
+            post.tags = tags_opt;
+        }
+        post.edited_at = Some(ic_cdk::api::time());
+        update_post_html(&mut state, post_id);
+        index_post(&mut state, post_id);
+        index_post_tags(&mut state, post_id);
+        update_post_mentions(&mut state, post_id, profile_id);
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+        update_feeds(&state);
 
         Ok(())
     })
 }
 
+#[update]
+#[candid_method(update)]
+fn edit_reply(reply_id: u64, text: String) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let reply_opt = state.replies.get(&reply_id);
+        if reply_opt.is_none() {
+            return Err("Reply does not exist".to_owned());
+        }
+        let reply = reply_opt.cloned().unwrap();
+
+        let (profile_id, _) = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().first_key_value().unwrap();
+        if !caller_can_hide_content && state.indexes.active_principal.get(&caller) != Some(profile_id) {
+            return Err("Caller is not the author".to_owned());
+        }
+        let profile_id = profile_id.to_owned();
+
+        // store previous version
+        let revision_id = uuid(&mut state);
+        let revision = ReplyRevision {
+            text: reply.text.to_owned(),
+            timestamp: reply.edited_at.unwrap_or(reply.timestamp)
+        };
+        state.reply_revisions.insert(revision_id, revision);
+        state.relations.reply_id_to_reply_revision_id.insert(reply_id, revision_id);
+
+        unindex_reply(&mut state, reply_id);
+        let reply = state.replies.get_mut(&reply_id).unwrap();
+        reply.text = text;
+        reply.edited_at = Some(ic_cdk::api::time());
+        update_reply_html(&mut state, reply_id);
+        index_reply(&mut state, reply_id);
+        update_reply_mentions(&mut state, reply_id, profile_id);
+
+        let post_id = state.relations.reply_id_to_post_id.forward.get(&reply_id).unwrap().first_key_value().unwrap().0.to_owned();
+        update_post_page(&state, post_id);
+        update_post_feed(&state, post_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn update_max_reply_depth(max_reply_depth: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_community = caller_has_permission(&caller, Permission::ManageCommunity);
+
+    if !caller_can_manage_community {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.max_reply_depth = Some(max_reply_depth);
+        Ok(())
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_max_reply_depth() -> u64 {
+    STATE.with(|s| s.borrow().max_reply_depth.unwrap_or(DEFAULT_MAX_REPLY_DEPTH))
+}
+
+#[update]
+#[candid_method(update)]
+fn create_category(name: String, description: String, order: u64, admin_only: bool) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+
+    if !caller_can_manage_categories {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    if name.trim().is_empty() {
+        return Err("Category name cannot be empty".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let category_id = uuid(&mut state);
+        let category = Category {
+            name,
+            description,
+            order,
+            admin_only,
+            timestamp: ic_cdk::api::time()
+        };
+        state.categories.insert(category_id, category);
+
+        Ok(category_id)
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn update_category(category_id: u64, name: String, description: String, order: u64, admin_only: bool) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+
+    if !caller_can_manage_categories {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    if name.trim().is_empty() {
+        return Err("Category name cannot be empty".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let category_opt = state.categories.get_mut(&category_id);
+        if category_opt.is_none() {
+            return Err("Category does not exist".to_owned());
+        }
+        let category = category_opt.unwrap();
+        category.name = name;
+        category.description = description;
+        category.order = order;
+        category.admin_only = admin_only;
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn delete_category(category_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+
+    if !caller_can_manage_categories {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if state.categories.remove(&category_id).is_none() {
+            return Err("Category does not exist".to_owned());
+        }
+
+        // posts of the category become uncategorized
+        let post_ids = state.relations.category_id_to_post_id.forward.get(&category_id).cloned().unwrap_or_default();
+        for (post_id, _) in post_ids {
+            state.relations.category_id_to_post_id.remove(category_id, post_id);
+        }
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn move_post(post_id: u64, category_id_opt: Option<u64>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_categories = caller_has_permission(&caller, Permission::ManageCategories);
+
+    if !caller_can_manage_categories {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if !state.posts.contains_key(&post_id) {
+            return Err("Post does not exist".to_owned());
+        }
+        if let Some(category_id) = category_id_opt {
+            if !state.categories.contains_key(&category_id) {
+                return Err("Category does not exist".to_owned());
+            }
+        }
+
+        if let Some(prev_category_id) = get_post_category_id(&state, &post_id) {
+            state.relations.category_id_to_post_id.remove(prev_category_id, post_id);
+        }
+        if let Some(category_id) = category_id_opt {
+            state.relations.category_id_to_post_id.insert(category_id, post_id);
+        }
+
+        Ok(())
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_categories() -> Vec<CategoryResponse> {
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let mut categories = state
+            .categories
+            .iter()
+            .map(|(category_id, category)| CategoryResponse {
+                category_id: category_id.to_owned(),
+                name: category.name.to_owned(),
+                description: category.description.to_owned(),
+                order: category.order,
+                admin_only: category.admin_only,
+                timestamp: category.timestamp
+            })
+            .collect::<Vec<_>>();
+        categories.sort_by_key(|category| (category.order, category.category_id));
+        categories
+    })
+}
+
+fn get_post_category_id(state: &State, post_id: &u64) -> Option<u64> {
+    state
+        .relations
+        .category_id_to_post_id
+        .backward
+        .get(post_id)
+        .and_then(|category_ids| category_ids.first_key_value())
+        .map(|(category_id, _)| category_id.to_owned())
+}
+
 #[query]
 #[candid_method(query)]
 fn get_profile_by_auth(authentication: AuthenticationWithAddress) -> Option<ProfileWithStatsResponse> {
@@ -299,38 +974,60 @@ fn get_profile_by_auth(authentication: AuthenticationWithAddress) -> Option<Prof
         if profile_id_opt == None {
             return None;
         }
-        let profile_id = profile_id_opt.unwrap();
-        let profile  = state.profiles.get(profile_id).unwrap();
-        let user_roles = get_user_roles(&profile.active_principal).unwrap();
-
-        let total_posts =  state.relations.profile_id_to_post_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
-        let total_replies =  state.relations.profile_id_to_reply_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
-        let posts_likes = state.relations.profile_id_to_liked_post_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
-        let replies_likes = state.relations.profile_id_to_liked_reply_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
-        let total_likes = replies_likes + posts_likes;
+        Some(get_profile_with_stats(&state, profile_id_opt.unwrap()))
+    })
+}
 
-        Some(ProfileWithStatsResponse {
-            name: profile.name.to_owned(),
-            description: profile.description.to_owned(),
-            authentication: profile.authentication.to_owned(),
-            active_principal: profile.active_principal.to_owned(),
-            roles: user_roles,
-            last_login: profile.last_login,
-            join_date: profile.timestamp,
-            total_likes,
-            total_posts,
-            total_replies
-        })
+#[query]
+#[candid_method(query)]
+fn get_profile_by_handle(handle: String) -> Option<ProfileWithStatsResponse> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let profile_id_opt = state.indexes.handle.get(&handle_key(&handle));
+        if profile_id_opt == None {
+            return None;
+        }
+        Some(get_profile_with_stats(&state, profile_id_opt.unwrap()))
     })
 }
 
+fn get_profile_with_stats(state: &State, profile_id: &u64) -> ProfileWithStatsResponse {
+    let profile  = state.profiles.get(profile_id).unwrap();
+    let user_roles = get_profile_roles(state, profile_id);
+
+    let total_posts =  state.relations.profile_id_to_post_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
+    let total_replies =  state.relations.profile_id_to_reply_id.forward.get(profile_id).map(|x| x.len()).unwrap_or(0) as u64;
+    let total_likes = get_profile_likes_count(state, profile_id);
+    let total_followers = get_followers_count(state, profile_id);
+    let total_following = get_following_count(state, profile_id);
+
+    ProfileWithStatsResponse {
+        name: profile.name.to_owned(),
+        description: profile.description.to_owned(),
+        authentication: profile.authentication.to_owned(),
+        active_principal: profile.active_principal.to_owned(),
+        roles: user_roles,
+        last_login: profile.last_login,
+        join_date: profile.timestamp,
+        total_likes,
+        total_posts,
+        total_replies,
+        total_followers,
+        total_following
+    }
+}
+
 #[update]
 #[candid_method(update)]
 fn like_post(post_id: u64) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         // check profile and post
-        let caller = ic_cdk::caller();
         if !state.indexes.active_principal.contains_key(&caller) {
             return Err("Profile does not exist".to_owned());
         }
@@ -339,31 +1036,12 @@ fn like_post(post_id: u64) -> Result<u64, String> {
         }
         // check already liked
         let profile_id = state.indexes.active_principal.get(&caller).unwrap().to_owned();
-        if state.indexes.has_liked_post.contains_key(&(profile_id.to_owned(), post_id.to_owned())) {
+        if get_reaction_id(&state, &profile_id, &post_id, &ReactionKind::Like).is_some() {
             return Err("Liked already".to_owned());
         }
         // insert like
         let liked_post_id = uuid(&mut state);
-        let liked_post = LikedPost {timestamp: ic_cdk::api::time() };
-        state.liked_posts.insert(liked_post_id.to_owned(), liked_post);
-        state.relations.post_id_to_liked_post_id.insert(post_id, liked_post_id.to_owned());
-        state.relations.profile_id_to_liked_post_id.insert(profile_id, liked_post_id.to_owned());
-        state.indexes.has_liked_post.insert((profile_id.to_owned(), post_id.to_owned()), ());
-
-        let profile_ids = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().to_owned();
-        let (profile_id, _) = profile_ids.first_key_value().unwrap();
-        let post_likes = state.relations.post_id_to_liked_post_id.forward.get(&post_id).unwrap().len() as u64;
-        let most_liked_posts_opt = state.indexes.most_liked_posts.get(profile_id);
-        if most_liked_posts_opt.is_none() {
-            let mut new_entry: BTreeSet<ValueEntry<u64, u64>> = BTreeSet::new();
-            new_entry.insert(ValueEntry::new(post_id, post_likes));
-            state.indexes.most_liked_posts.insert(profile_id.to_owned(), new_entry.to_owned());
-        } else {
-            let mut most_liked_posts = most_liked_posts_opt.unwrap().clone();
-            most_liked_posts.retain(|a| {a.get().0 != &post_id});
-            most_liked_posts.insert(ValueEntry::new(post_id, post_likes));
-            state.indexes.most_liked_posts.insert(profile_id.to_owned(), most_liked_posts.to_owned());
-        }
+        add_post_reaction(&mut state, liked_post_id, profile_id, post_id, ReactionKind::Like);
 
         Ok(liked_post_id)
     })
@@ -375,34 +1053,19 @@ fn unlike_post(liked_post_id: u64) -> Result<(), String> {
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         // check like
-        if !state.liked_posts.contains_key(&liked_post_id) {
+        let reaction_opt = state.reactions.get(&liked_post_id);
+        if reaction_opt.is_none() || reaction_opt.unwrap().kind != ReactionKind::Like || !state.relations.post_id_to_reaction_id.backward.contains_key(&liked_post_id) {
             return Err("Like does not exist".to_owned());
         }
         // check profile
         let caller = ic_cdk::caller();
-        let profile_ids =  state.relations.profile_id_to_liked_post_id.backward.get(&liked_post_id).unwrap().to_owned();
+        let profile_ids = state.relations.profile_id_to_reaction_id.backward.get(&liked_post_id).unwrap().to_owned();
         let (profile_id, _) = profile_ids.first_key_value().unwrap();
-        let caller_profile_id = state.indexes.active_principal.get(&caller).unwrap();
-        if profile_id != caller_profile_id {
+        if state.indexes.active_principal.get(&caller) != Some(profile_id) {
             return Err("Invalid caller".to_owned());
         }
         // remove like
-        let post_ids = state.relations.post_id_to_liked_post_id.backward.get(&liked_post_id).unwrap().to_owned();
-        let (post_id, _) = post_ids.first_key_value().unwrap();
-        state.relations.post_id_to_liked_post_id.remove(post_id.to_owned(), liked_post_id.to_owned());
-        state.relations.profile_id_to_liked_post_id.remove(profile_id.to_owned(), liked_post_id.to_owned());
-        state.indexes.has_liked_post.remove(&(profile_id.to_owned(), post_id.to_owned()));
-        state.liked_posts.remove(&liked_post_id);
-
-        let post_likes_opt = state.relations.post_id_to_liked_post_id.forward.get(post_id);
-        let mut most_liked_posts = state.indexes.most_liked_posts.get(profile_id).unwrap().clone();
-        most_liked_posts.retain(|a| {&a.get().0 != &post_id});
-
-        if post_likes_opt.is_some(){
-            most_liked_posts.insert(ValueEntry::new(post_id.to_owned(), post_likes_opt.unwrap().len() as u64));
-        }
-
-        state.indexes.most_liked_posts.insert(profile_id.to_owned(), most_liked_posts.to_owned());
+        remove_reaction(&mut state, liked_post_id);
 
         Ok(())
     })
@@ -411,10 +1074,14 @@ fn unlike_post(liked_post_id: u64) -> Result<(), String> {
 #[update]
 #[candid_method(update)]
 fn like_reply(reply_id: u64) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         // check profile and post
-        let caller = ic_cdk::caller();
         if !state.indexes.active_principal.contains_key(&caller) {
             return Err("Profile does not exist".to_owned());
         }
@@ -423,31 +1090,13 @@ fn like_reply(reply_id: u64) -> Result<u64, String> {
         }
         // check already liked
         let profile_id = state.indexes.active_principal.get(&caller).unwrap().to_owned();
-        if state.indexes.has_liked_reply.contains_key(&(profile_id.to_owned(), reply_id.to_owned())) {
+        if get_reaction_id(&state, &profile_id, &reply_id, &ReactionKind::Like).is_some() {
             return Err("Liked already".to_owned());
         }
         // insert like
         let liked_reply_id = uuid(&mut state);
-        let liked_reply = LikedReply {timestamp: ic_cdk::api::time() };
-        state.liked_replies.insert(liked_reply_id.to_owned(), liked_reply);
-        state.relations.reply_id_to_liked_reply_id.insert(reply_id, liked_reply_id.to_owned());
-        state.relations.profile_id_to_liked_reply_id.insert(profile_id, liked_reply_id.to_owned());
-        state.indexes.has_liked_reply.insert((profile_id.to_owned(), reply_id.to_owned()), ());
+        add_reply_reaction(&mut state, liked_reply_id, profile_id, reply_id, ReactionKind::Like);
 
-        let profile_ids = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().to_owned();
-        let (profile_id, _) = profile_ids.first_key_value().unwrap();
-        let reply_likes = state.relations.reply_id_to_liked_reply_id.forward.get(&reply_id).unwrap().len() as u64;
-        let most_liked_replies_opt = state.indexes.most_liked_replies.get(profile_id);
-        if most_liked_replies_opt.is_none() {
-            let mut new_entry: BTreeSet<ValueEntry<u64, u64>> = BTreeSet::new();
-            new_entry.insert(ValueEntry::new(reply_id, reply_likes));
-            state.indexes.most_liked_replies.insert(profile_id.to_owned(), new_entry.to_owned());
-        } else {
-            let mut most_liked_replies = most_liked_replies_opt.unwrap().clone();
-            most_liked_replies.retain(|r|r.get().0 != &reply_id);
-            most_liked_replies.insert(ValueEntry::new(reply_id, reply_likes));
-            state.indexes.most_liked_replies.insert(profile_id.to_owned(), most_liked_replies.to_owned());
-        }
         Ok(liked_reply_id)
     })
 }
@@ -458,32 +1107,20 @@ fn unlike_reply(liked_reply_id: u64) -> Result<(), String> {
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         // check like
-        if !state.liked_replies.contains_key(&liked_reply_id) {
+        let reaction_opt = state.reactions.get(&liked_reply_id);
+        if reaction_opt.is_none() || reaction_opt.unwrap().kind != ReactionKind::Like || !state.relations.reply_id_to_reaction_id.backward.contains_key(&liked_reply_id) {
             return Err("Like does not exist".to_owned());
         }
         // check profile
         let caller = ic_cdk::caller();
-        let profile_ids =  state.relations.profile_id_to_liked_reply_id.backward.get(&liked_reply_id).unwrap().to_owned();
+        let profile_ids = state.relations.profile_id_to_reaction_id.backward.get(&liked_reply_id).unwrap().to_owned();
         let (profile_id, _) = profile_ids.first_key_value().unwrap();
-        let caller_profile_id = state.indexes.active_principal.get(&caller).unwrap();
-        if profile_id != caller_profile_id {
+        if state.indexes.active_principal.get(&caller) != Some(profile_id) {
             return Err("Invalid caller".to_owned());
         }
         // remove like
-        let reply_ids = state.relations.reply_id_to_liked_reply_id.backward.get(&liked_reply_id).unwrap().to_owned();
-        let (reply_id, _) = reply_ids.first_key_value().unwrap();
-        state.relations.reply_id_to_liked_reply_id.remove(reply_id.to_owned(), liked_reply_id.to_owned());
-        state.relations.profile_id_to_liked_reply_id.remove(profile_id.to_owned(), liked_reply_id.to_owned());
-        state.indexes.has_liked_reply.remove(&(profile_id.to_owned(), reply_id.to_owned()));
-        state.liked_replies.remove(&liked_reply_id);
-
-        let reply_likes_opt = state.relations.reply_id_to_liked_reply_id.forward.get(reply_id);
-        let mut most_liked_replies = state.indexes.most_liked_replies.get(profile_id).unwrap().clone();
-        most_liked_replies.retain(|a| {&a.get().0 != &reply_id});
-        if reply_likes_opt.is_some() {
-            most_liked_replies.insert(ValueEntry::new(reply_id.to_owned(), reply_likes_opt.unwrap().len() as u64));
-        }
-        state.indexes.most_liked_replies.insert(profile_id.to_owned(), most_liked_replies.to_owned());
+        remove_reaction(&mut state, liked_reply_id);
+
         Ok(())
     })
 }
@@ -492,65 +1129,213 @@ fn unlike_reply(liked_reply_id: u64) -> Result<(), String> {
 #[candid_method(query)]
 fn get_posts() -> Vec<PostSummary> {
     let caller = ic_cdk::caller();
-    let caller_roles_opt = get_user_roles(&caller);
-    let caller_is_admin = match caller_roles_opt {
-        Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-        None => false
-    };
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
     STATE.with(|s| {
         let state = &mut s.borrow_mut();
 
-        state
+        let mut posts = state
             .posts
             .iter()
             .filter_map(|(post_id, post)| {
-                if !caller_is_admin && post.status == PostStatus::Hidden {
+                if !caller_can_hide_content && post.status == PostStatus::Hidden {
                     return None;
                 }
-                let replies_opt = state.relations.reply_id_to_post_id.backward.get(&post_id);
-
-                let replies_count = if replies_opt == None {
-                    0
-                } else {
-                    replies_opt.unwrap().iter().filter_map(|(reply_id, _)| {
-                        let reply = state.replies.get(reply_id).unwrap();
-                        if reply.status == ReplyStatus::Hidden {
-                            return None;
-                        } else {
-                            return Some(reply_id);
-                        }
-                    }).collect::<Vec<_>>().len()
-                };
+                Some(get_post_summary(state, post_id))
+            })
+            .collect::<Vec<_>>();
+        posts.sort_by_key(|post| !post.flags.pinned);
+        posts
+    })
+}
 
-                let last_activity = if replies_opt == None {
-                    0
-                } else {
-                    let (reply_id, _) = replies_opt.unwrap().last_key_value().unwrap();
-                    state.replies.get(reply_id).unwrap().timestamp
-                };
+#[query]
+#[candid_method(query)]
+fn get_posts_page(sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> PostsPage {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
-                let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+    STATE.with(|s| {
+        let state = s.borrow();
+        get_pinned_first_page(&state, sort, cursor_opt, limit_opt, caller_can_hide_content, |_| true)
+    })
+}
 
-                let profile =  state.profiles.get(&profile_id).unwrap();
+#[query]
+#[candid_method(query)]
+fn get_category_posts_page(category_id: u64, sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> Result<PostsPage, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
-                let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+    STATE.with(|s| {
+        let state = s.borrow();
 
-                Some(PostSummary {
-                    title: post.title.to_owned(),
-                    post_id: post_id.to_owned(),
-                    description: post.description.to_owned(),
-                    timestamp: post.timestamp,
-                    replies_count: replies_count as u64,
-                    last_activity,
-                    authentication,
-                    status: post.status.to_owned()
-                })
-            })
-            .collect::<Vec<_>>()
+        if !state.categories.contains_key(&category_id) {
+            return Err("Category does not exist".to_owned());
+        }
+
+        let post_ids = state.relations.category_id_to_post_id.forward.get(&category_id).cloned().unwrap_or_default();
+        Ok(get_pinned_first_page(&state, sort, cursor_opt, limit_opt, caller_can_hide_content, |post_id| post_ids.contains_key(post_id)))
     })
 }
 
+// pinned posts are returned separately on the first page so they do not count towards the limit
+fn get_pinned_first_page(state: &State, sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>, include_hidden: bool, filter: impl Fn(&u64) -> bool) -> PostsPage {
+    let is_first_page = cursor_opt.is_none();
+
+    let mut page = get_page(state, sort, cursor_opt, limit_opt, include_hidden, |post_id| !state.indexes.pinned_posts.contains(post_id) && filter(post_id));
+    if is_first_page {
+        let index = get_sort_index(state, sort);
+        let mut pinned_post_ids = state.indexes.pinned_posts
+            .iter()
+            .filter(|post_id| include_hidden || state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+            .filter(|post_id| filter(post_id))
+            .collect::<Vec<_>>();
+        pinned_post_ids.sort_by_key(|post_id| std::cmp::Reverse((index.values.get(post_id).cloned().unwrap_or(0), post_id.to_owned())));
+        page.pinned_posts = pinned_post_ids.iter().map(|post_id| get_post_summary(state, post_id)).collect();
+    }
+    page
+}
+
+fn get_post_flags(state: &State, post_id: &u64) -> PostFlags {
+    state.posts.get(post_id).unwrap().flags.to_owned().unwrap_or_default()
+}
+
+fn get_sort_index(state: &State, sort: PostSort) -> &OrderedIndex {
+    match sort {
+        PostSort::Newest => &state.indexes.posts_by_timestamp,
+        PostSort::LastActivity => &state.indexes.posts_by_last_activity,
+        PostSort::MostLiked => &state.indexes.posts_by_likes,
+        PostSort::MostReplied => &state.indexes.posts_by_replies,
+    }
+}
+
+// paginates posts of the sort index that pass the filter
+fn get_page(state: &State, sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>, include_hidden: bool, filter: impl Fn(&u64) -> bool) -> PostsPage {
+    let index = get_sort_index(state, sort);
+
+    // entries are iterated in descending order starting after the cursor
+    let entries: Box<dyn Iterator<Item = &(u64, u64)>> = match cursor_opt {
+        Some(cursor) => Box::new(index.entries.range(..(cursor.value, cursor.post_id)).rev()),
+        None => Box::new(index.entries.iter().rev()),
+    };
+
+    let limit = limit_opt.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+    let mut page_entries = entries
+        .filter(|(_, post_id)| include_hidden || state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+        .filter(|(_, post_id)| filter(post_id))
+        .take(limit + 1)
+        .collect::<Vec<_>>();
+
+    let next_cursor = if page_entries.len() > limit {
+        page_entries.truncate(limit);
+        page_entries.last().map(|(value, post_id)| PostCursor { value: value.to_owned(), post_id: post_id.to_owned() })
+    } else {
+        None
+    };
+
+    let posts = page_entries
+        .iter()
+        .map(|(_, post_id)| get_post_summary(state, post_id))
+        .collect::<Vec<_>>();
+
+    PostsPage { posts, next_cursor, pinned_posts: vec![] }
+}
+
+// updates the ordered indexes used by `get_posts_page`
+fn update_post_indexes(state: &mut State, post_id: u64) {
+    let timestamp = state.posts.get(&post_id).unwrap().timestamp;
+    let post_stats = state.post_stats.get(&post_id).cloned().unwrap();
+
+    state.indexes.posts_by_timestamp.insert(post_id, timestamp);
+    state.indexes.posts_by_last_activity.insert(post_id, post_stats.last_activity.max(timestamp));
+    state.indexes.posts_by_likes.insert(post_id, post_stats.likes_count);
+    state.indexes.posts_by_replies.insert(post_id, post_stats.replies_count);
+}
+
+// the pinned posts are counted once for the state of a version without their index
+fn migrate_pinned_posts(state: &mut State) {
+    if !state.indexes.pinned_posts.is_missing() {
+        return;
+    }
+    let pinned_post_ids = state.posts.keys().filter(|post_id| get_post_flags(state, post_id).pinned).cloned().collect::<Vec<_>>();
+    state.indexes.pinned_posts.extend(pinned_post_ids);
+}
+
+// the indexes are saved with the state, they are only built for the posts of a version without them
+fn migrate_post_indexes(state: &mut State) {
+    let indexes = &mut state.indexes;
+    if ![&indexes.posts_by_timestamp, &indexes.posts_by_last_activity, &indexes.posts_by_likes, &indexes.posts_by_replies].iter().any(|index| index.is_missing()) {
+        return;
+    }
+    for index in [&mut indexes.posts_by_timestamp, &mut indexes.posts_by_last_activity, &mut indexes.posts_by_likes, &mut indexes.posts_by_replies] {
+        **index = OrderedIndex::default();
+        index.start_rebuild();
+    }
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    migrate_in_batches(
+        post_ids,
+        |state, post_id| if state.posts.contains_key(&post_id) { update_post_indexes(state, post_id) },
+        |state| {
+            let indexes = &mut state.indexes;
+            for index in [&mut indexes.posts_by_timestamp, &mut indexes.posts_by_last_activity, &mut indexes.posts_by_likes, &mut indexes.posts_by_replies] {
+                index.finish_rebuild();
+            }
+        }
+    );
+}
+
+fn get_post_summary(state: &State, post_id: &u64) -> PostSummary {
+    let post = state.posts.get(post_id).unwrap();
+    let post_stats = state.post_stats.get(post_id).unwrap();
+
+    let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(post_id).unwrap().first_key_value().unwrap();
+
+    let profile =  state.profiles.get(profile_id).unwrap();
+
+    let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+
+    PostSummary {
+        title: post.title.to_owned(),
+        post_id: post_id.to_owned(),
+        description: post.description.to_owned(),
+        timestamp: post.timestamp,
+        replies_count: post_stats.replies_count,
+        likes_count: post_stats.likes_count,
+        last_activity: post_stats.last_activity,
+        authentication,
+        status: post.status.to_owned(),
+        category_id: get_post_category_id(state, post_id),
+        tags: get_post_tags(state, post_id),
+        flags: get_post_flags(state, post_id)
+    }
+}
+
+// computes the post aggregates from replies and likes, once for the state of a version without them,
+// synchronously as every post summary reads them
+fn migrate_post_stats(state: &mut State) {
+    if !state.post_stats.is_missing() {
+        return;
+    }
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    for post_id in post_ids {
+        let reply_ids = state.relations.reply_id_to_post_id.backward.get(&post_id).cloned().unwrap_or_default();
+
+        let replies_count = reply_ids
+            .keys()
+            .filter(|reply_id| state.replies.get(reply_id).unwrap().status == ReplyStatus::Visible)
+            .count() as u64;
+        let last_activity = reply_ids
+            .keys()
+            .map(|reply_id| state.replies.get(reply_id).unwrap().timestamp)
+            .max()
+            .unwrap_or(0);
+        let likes_count = get_post_likes_count(state, &post_id);
+
+        state.post_stats.insert(post_id, PostStats { replies_count, likes_count, last_activity });
+    }
+}
+
 #[query]
 #[candid_method(query)]
 fn get_profile() -> Result<ProfileResponse, String> {
@@ -564,7 +1349,7 @@ fn get_profile() -> Result<ProfileResponse, String> {
         }
         let profile_id = profile_id_opt.unwrap();
         let profile = state.profiles.get(profile_id).unwrap();
-        let user_roles = get_user_roles(&caller).unwrap();
+        let user_roles = get_profile_roles(&state, profile_id);
         Ok(ProfileResponse {
             name: profile.name.to_owned(),
             description: profile.description.to_owned(),
@@ -579,11 +1364,7 @@ fn get_profile() -> Result<ProfileResponse, String> {
 #[candid_method(query)]
 fn get_post(post_id: u64) -> Result<PostResponse, String> {
     let caller = ic_cdk::caller();
-    let caller_roles_opt = get_user_roles(&caller);
-    let caller_is_admin = match caller_roles_opt {
-        Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-        None => false
-    };
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
     STATE.with(|s| {
         let state = s.borrow();
@@ -593,39 +1374,25 @@ fn get_post(post_id: u64) -> Result<PostResponse, String> {
         }
 
         let post = post_opt.unwrap();
-        if !caller_is_admin && post.status == PostStatus::Hidden {
+        if !caller_can_hide_content && post.status == PostStatus::Hidden {
             return Err("This post is hiden".to_owned());
         }
 
-        let replies_opt = state.relations.reply_id_to_post_id.backward.get(&post_id);
-
-        let replies = if replies_opt == None {
-            vec![]
-        } else {
-            replies_opt.unwrap().iter().filter_map(|(reply_id, _)| {
+        let replies = get_thread_reply_ids(&state, &post_id)
+            .iter()
+            .filter_map(|(reply_id, depth)| {
                 let reply = state.replies.get(reply_id).unwrap();
-                if !caller_is_admin && reply.status == ReplyStatus::Hidden {
+                if !caller_can_hide_content && reply.status == ReplyStatus::Hidden {
                     return None;
                 }
                 let (profile_id, _) = state.relations.profile_id_to_reply_id.backward.get(reply_id).unwrap().first_key_value().unwrap();
                 let profile = state.profiles.get(&profile_id).unwrap();
-                let liked_reply_ids_opt = state.relations.reply_id_to_liked_reply_id.forward.get(reply_id);
-                let likes = if liked_reply_ids_opt.is_none() {
-                    vec![]
-                } else {
-                    liked_reply_ids_opt.unwrap().iter().map(|(liked_reply_id, _)| {
-                        let profile_ids = state.relations.profile_id_to_liked_reply_id.backward.get(liked_reply_id).unwrap().to_owned();
-                        let (profile_id, _) = profile_ids.first_key_value().unwrap();
-                        let profile = state.profiles.get(profile_id).unwrap();
-                        let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-                        (liked_reply_id.to_owned(), authentication)
-                    }).collect::<Vec<_>>()
-                };
+                let likes = get_reply_likes(&state, reply_id);
 
                 let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-                Some(ReplyResponse { text: reply.text.to_owned(), timestamp: reply.timestamp, authentication , reply_id: reply_id.to_owned(), status: reply.status.to_owned(), likes: likes })
-            }).collect::<Vec<_>>()
-        };
+                let parent_reply_id = get_parent_reply_id(&state, reply_id);
+                Some(ReplyResponse { text: reply.text.to_owned(), timestamp: reply.timestamp, authentication , reply_id: reply_id.to_owned(), status: reply.status.to_owned(), likes: likes, edited_at: reply.edited_at, parent_reply_id, depth: depth.to_owned(), reactions: get_reply_reactions(&state, reply_id, &caller), mentions: get_reply_mentions(&state, reply_id), text_html: get_reply_html(&state, reply_id), attachments: get_reply_attachments(&state, reply_id) })
+            }).collect::<Vec<_>>();
 
 
 
@@ -634,18 +1401,7 @@ fn get_post(post_id: u64) -> Result<PostResponse, String> {
         let profile = state.profiles.get(&profile_id).unwrap();
         let authentication  = get_authentication_with_address(&profile.authentication, &profile.active_principal);
 
-        let liked_post_ids_opt =  state.relations.post_id_to_liked_post_id.forward.get(&post_id);
-        let likes = if liked_post_ids_opt.is_none() {
-            vec![]
-        } else  {
-            liked_post_ids_opt.unwrap().to_owned().iter().map(|(liked_post_id, _)| {
-                let profile_ids_opt = state.relations.profile_id_to_liked_post_id.backward.get(liked_post_id).unwrap().to_owned();
-                let (profile_id, _) = profile_ids_opt.first_key_value().unwrap();
-                let profile = state.profiles.get(profile_id).unwrap();
-                let authentication  = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-                (liked_post_id.to_owned(), authentication)
-            }).collect::<Vec<_>>()
-        };
+        let likes = get_post_likes(&state, &post_id);
 
         let post_result = PostResponse {
             replies,
@@ -655,7 +1411,15 @@ fn get_post(post_id: u64) -> Result<PostResponse, String> {
             likes: likes,
             authentication,
             status: post.status.to_owned(),
-            post_id: post_id.to_owned()
+            post_id: post_id.to_owned(),
+            edited_at: post.edited_at,
+            tags: get_post_tags(&state, &post_id),
+            poll: get_poll_response(&state, &post_id, &caller),
+            reactions: get_post_reactions(&state, &post_id, &caller),
+            mentions: get_post_mentions(&state, &post_id),
+            description_html: get_post_html(&state, &post_id),
+            attachments: get_post_attachments(&state, &post_id),
+            flags: get_post_flags(&state, &post_id)
         };
         Ok(post_result)
     })
@@ -682,65 +1446,17 @@ fn get_most_recent_posts(authentication: AuthenticationWithAddress) -> Result<Ve
         }
 
         let profile = state.profiles.get(profile_id_opt.unwrap()).unwrap();
-        let caller_roles_opt = get_user_roles(&profile.active_principal);
-        let caller_is_admin = match caller_roles_opt {
-            Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-            None => false
-        };
+        let caller_can_hide_content = caller_has_permission(&profile.active_principal, Permission::HideContent);
 
         let mut user_posts =  post_ids_opt
             .unwrap()
             .iter()
             .filter_map(|(post_id, _)| {
                 let post = state.posts.get(&post_id.to_owned()).unwrap();
-                if !caller_is_admin && post.status == PostStatus::Hidden {
+                if !caller_can_hide_content && post.status == PostStatus::Hidden {
                     return None;
                 }
-                let replies_opt = state.relations.reply_id_to_post_id.backward.get(&post_id);
-
-                let replies_count = if replies_opt == None {
-                    0
-                } else {
-                    replies_opt.unwrap().iter().filter_map(|(reply_id, _)| {
-                        let reply = state.replies.get(reply_id).unwrap();
-                        if reply.status == ReplyStatus::Hidden {
-                            return None;
-                        } else {
-                            return Some(reply_id);
-                        }
-                    }).collect::<Vec<_>>().len()
-                };
-
-                let last_activity = if replies_opt == None {
-                    0
-                } else {
-                    let (reply_id, _) = replies_opt.unwrap().last_key_value().unwrap();
-                    state.replies.get(reply_id).unwrap().timestamp
-                };
-
-                // FIX
-                let (profile_id, _) = state
-                    .relations
-                    .profile_id_to_post_id
-                    .backward
-                    .get(&post_id)
-                    .unwrap()
-                    .first_key_value()
-                    .unwrap();
-
-                let profile = state.profiles.get(&profile_id).unwrap();
-                let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-
-                Some(PostSummary {
-                    post_id: post_id.to_owned(),
-                    title: post.title.to_owned(),
-                    description: post.description.to_owned(),
-                    timestamp: post.timestamp.to_owned(),
-                    replies_count: replies_count as u64,
-                    last_activity,
-                    authentication,
-                    status: post.status.to_owned()
-                })
+                Some(get_post_summary(&state, post_id))
             }).collect::<Vec<_>>();
 
         user_posts.sort_by(|a, b|b.timestamp.partial_cmp(&a.timestamp).unwrap());
@@ -751,6 +1467,7 @@ fn get_most_recent_posts(authentication: AuthenticationWithAddress) -> Result<Ve
 #[query]
 #[candid_method(query)]
 fn get_most_liked_posts(authentication: AuthenticationWithAddress) -> Result<Vec<PostResponse>, String> {
+    let caller = ic_cdk::caller();
 
     STATE.with(|s| {
         let state = s.borrow();
@@ -771,18 +1488,7 @@ fn get_most_liked_posts(authentication: AuthenticationWithAddress) -> Result<Vec
             let (post_id, _) = entry.get();
             let posts = state.posts.get(&post_id).unwrap();
 
-            let liked_post_ids_opt =  state.relations.post_id_to_liked_post_id.forward.get(&post_id);
-            let likes = if liked_post_ids_opt.is_none() {
-                vec![]
-            } else  {
-                liked_post_ids_opt.unwrap().to_owned().iter().map(|(liked_post_id, _)| {
-                    let profile_ids_opt = state.relations.profile_id_to_liked_post_id.backward.get(liked_post_id).unwrap().to_owned();
-                    let (profile_id, _) = profile_ids_opt.first_key_value().unwrap();
-                    let profile = state.profiles.get(profile_id).unwrap();
-                    let authentication  = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-                    (liked_post_id.to_owned(), authentication)
-                }).collect::<Vec<_>>()
-            };
+            let likes = get_post_likes(&state, post_id);
 
             let respond = PostResponse {
                 title: posts.title.to_owned(),
@@ -792,7 +1498,15 @@ fn get_most_liked_posts(authentication: AuthenticationWithAddress) -> Result<Vec
                 status: posts.status.to_owned(),
                 replies: vec![],
                 authentication: authentication.to_owned(),
-                likes: likes
+                likes: likes,
+                edited_at: posts.edited_at,
+                tags: get_post_tags(&state, post_id),
+                poll: get_poll_response(&state, post_id, &caller),
+                reactions: get_post_reactions(&state, post_id, &caller),
+                mentions: get_post_mentions(&state, post_id),
+                description_html: get_post_html(&state, post_id),
+                attachments: get_post_attachments(&state, post_id),
+                flags: get_post_flags(&state, post_id)
             };
             result.push(respond);
         }
@@ -804,6 +1518,8 @@ fn get_most_liked_posts(authentication: AuthenticationWithAddress) -> Result<Vec
 #[query]
 #[candid_method(query)]
 fn get_most_liked_replies(authentication: AuthenticationWithAddress) -> Result<Vec<(u64, ReplyResponse)>, String> {
+    let caller = ic_cdk::caller();
+
     STATE.with(|s| {
         let state = s.borrow();
         let profile_id_opt = state.indexes.profile.get(&authentication);
@@ -826,18 +1542,7 @@ fn get_most_liked_replies(authentication: AuthenticationWithAddress) -> Result<V
             let (reply_id, _) = entry.get();
             let reply = state.replies.get(reply_id).unwrap();
 
-            let liked_reply_ids_opt =  state.relations.reply_id_to_liked_reply_id.forward.get(&reply_id);
-            let likes = if liked_reply_ids_opt.is_none() {
-                vec![]
-            } else  {
-                liked_reply_ids_opt.unwrap().to_owned().iter().map(|(liked_reply_id, _)| {
-                    let profile_ids_opt = state.relations.profile_id_to_liked_reply_id.backward.get(liked_reply_id).unwrap().to_owned();
-                    let (profile_id, _) = profile_ids_opt.first_key_value().unwrap();
-                    let profile = state.profiles.get(profile_id).unwrap();
-                    let authentication  = get_authentication_with_address(&profile.authentication, &profile.active_principal);
-                    (liked_reply_id.to_owned(), authentication)
-                }).collect::<Vec<_>>()
-            };
+            let likes = get_reply_likes(&state, reply_id);
 
             let response = ReplyResponse {
                 text: reply.text.to_owned(),
@@ -845,7 +1550,14 @@ fn get_most_liked_replies(authentication: AuthenticationWithAddress) -> Result<V
                 authentication: authentication.to_owned(),
                 reply_id: reply_id.to_owned(),
                 likes: likes,
-                status: reply.status.to_owned()
+                status: reply.status.to_owned(),
+                edited_at: reply.edited_at,
+                parent_reply_id: get_parent_reply_id(&state, reply_id),
+                depth: get_reply_depth(&state, reply_id),
+                reactions: get_reply_reactions(&state, reply_id, &caller),
+                mentions: get_reply_mentions(&state, reply_id),
+                text_html: get_reply_html(&state, reply_id),
+                attachments: get_reply_attachments(&state, reply_id)
             };
             let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(reply_id).unwrap().first_key_value().unwrap();
             result.push((post_id.to_owned(), response))
@@ -860,13 +1572,9 @@ fn get_hidden_posts() -> Result<Vec<PostResponse>, String> {
         let state = s.borrow();
 
         let caller = ic_cdk::caller();
-        let caller_roles_opt = get_user_roles(&caller);
-        let caller_is_admin = match caller_roles_opt {
-            Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-            None => false
-        };
-        if !caller_is_admin {
-            return Err("Caller is not admin".to_owned())
+        let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+        if !caller_can_hide_content {
+            return Err("Caller does not have permission".to_owned())
         }
 
         let hidden_post = state.posts
@@ -886,7 +1594,15 @@ fn get_hidden_posts() -> Result<Vec<PostResponse>, String> {
                     timestamp: post.timestamp.to_owned(),
                     status: post.status.to_owned(),
                     replies: vec![],
-                    likes: vec![]
+                    likes: vec![],
+                    edited_at: post.edited_at,
+                    tags: get_post_tags(&state, post_id),
+                    poll: get_poll_response(&state, post_id, &caller),
+                    reactions: get_post_reactions(&state, post_id, &caller),
+                    mentions: get_post_mentions(&state, post_id),
+                    description_html: get_post_html(&state, post_id),
+                    attachments: get_post_attachments(&state, post_id),
+                    flags: get_post_flags(&state, post_id)
                 };
                 Some(post_response)
             })
@@ -902,14 +1618,10 @@ fn get_hidden_replies() -> Result<Vec<(u64,ReplyResponse)>, String> {
         let state = s.borrow();
 
         let caller = ic_cdk::caller();
-        let caller_roles_opt = get_user_roles(&caller);
-        let caller_is_admin = match caller_roles_opt {
-            Some(caller_roles) => caller_roles.iter().any(|r| r == &UserRole::Admin),
-            None => false
-        };
+        let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
 
-        if !caller_is_admin {
-            return Err("Caller is not admin".to_owned())
+        if !caller_can_hide_content {
+            return Err("Caller does not have permission".to_owned())
         }
 
         let hidden_replies = state.replies
@@ -928,7 +1640,14 @@ fn get_hidden_replies() -> Result<Vec<(u64,ReplyResponse)>, String> {
                     authentication: authentication,
                     timestamp: reply.timestamp.to_owned(),
                     status: reply.status.to_owned(),
-                    likes: vec![]
+                    likes: vec![],
+                    edited_at: reply.edited_at,
+                    parent_reply_id: get_parent_reply_id(&state, reply_id),
+                    depth: get_reply_depth(&state, reply_id),
+                    reactions: get_reply_reactions(&state, reply_id, &caller),
+                    mentions: get_reply_mentions(&state, reply_id),
+                    text_html: get_reply_html(&state, reply_id),
+                    attachments: get_reply_attachments(&state, reply_id)
                 };
                 Some((post_id.to_owned(), reply_response))
             })
@@ -937,6 +1656,72 @@ fn get_hidden_replies() -> Result<Vec<(u64,ReplyResponse)>, String> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn get_post_revisions(post_id: u64) -> Result<Vec<PostRevision>, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        if !state.posts.contains_key(&post_id) {
+            return Err("Post does not exist".to_owned());
+        }
+
+        let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+        if !caller_can_hide_content && state.indexes.active_principal.get(&caller) != Some(profile_id) {
+            return Err("Caller is not the author".to_owned());
+        }
+
+        let revision_ids_opt = state.relations.post_id_to_post_revision_id.forward.get(&post_id);
+        if revision_ids_opt.is_none() {
+            return Ok(vec![]);
+        }
+
+        let mut revisions = revision_ids_opt
+            .unwrap()
+            .iter()
+            .map(|(revision_id, _)| state.post_revisions.get(revision_id).cloned().unwrap())
+            .collect::<Vec<_>>();
+        revisions.sort_by_key(|revision| revision.timestamp);
+        Ok(revisions)
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_reply_revisions(reply_id: u64) -> Result<Vec<ReplyRevision>, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        if !state.replies.contains_key(&reply_id) {
+            return Err("Reply does not exist".to_owned());
+        }
+
+        let (profile_id, _) = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().first_key_value().unwrap();
+        if !caller_can_hide_content && state.indexes.active_principal.get(&caller) != Some(profile_id) {
+            return Err("Caller is not the author".to_owned());
+        }
+
+        let revision_ids_opt = state.relations.reply_id_to_reply_revision_id.forward.get(&reply_id);
+        if revision_ids_opt.is_none() {
+            return Ok(vec![]);
+        }
+
+        let mut revisions = revision_ids_opt
+            .unwrap()
+            .iter()
+            .map(|(revision_id, _)| state.reply_revisions.get(revision_id).cloned().unwrap())
+            .collect::<Vec<_>>();
+        revisions.sort_by_key(|revision| revision.timestamp);
+        Ok(revisions)
+    })
+}
+
 #[query]
 #[candid_method(query)]
 fn get_metadata() -> Result<Metadata, String> {
@@ -986,9 +1771,47 @@ fn post_upgrade() {
     ic_certified_assets::post_upgrade(s_prev.storage);
     STATE.with(|s| *s.borrow_mut() = s_prev.state);
 
+    // set default names
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        for profile in state.profiles.values_mut().filter(|p| p.name.is_empty()) {
+            let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+            profile.name = address_to_name(&get_address(&authentication));
+        }
+    });
+
+    // set default flags of older posts
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        for post in state.posts.values_mut().filter(|p| p.flags.is_none()) {
+            post.flags = Some(PostFlags::default());
+        }
+    });
+
+    // migrate the state of older versions, indexes and html are built in batches on timers
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        migrate_likes(&mut state);
+        migrate_post_stats(&mut state);
+        migrate_post_indexes(&mut state);
+        migrate_pinned_posts(&mut state);
+        migrate_index(&mut state);
+        migrate_tag_index(&mut state);
+        render_missing_html(&mut state);
+    });
+
+    // restart poll and draft timers
+    STATE.with(|s| set_poll_timers(&s.borrow()));
+    STATE.with(|s| set_draft_timers(&s.borrow()));
+
     // finalize upgrade
     update_metadata();
     replace_assets_from_temp();
+
+    // post pages are rendered again with the new frontend
+    STATE.with(|s| render_post_files(&s.borrow()));
+    STATE.with(|s| update_feeds(&s.borrow()));
+    STATE.with(|s| render_crawler_files(&s.borrow()));
 }
 
 #[query]
@@ -996,9 +1819,25 @@ fn post_upgrade() {
 fn http_request(
     req: ic_certified_assets::types::HttpRequest,
 ) -> ic_certified_assets::types::HttpResponse {
+    if is_api_request(&req) {
+        if is_preflight_request(&req) || is_uncertified_request(&req) {
+            return handle_api_request(&req);
+        }
+        return build_upgrade();
+    }
     return ic_certified_assets::http_request_handle(req);
 }
 
+// api requests upgraded by the gateway are answered as update calls so the response is certified,
+// these go through consensus and are paid from the canister cycles (clients can opt out with ?uncertified=true)
+#[update]
+#[candid_method(update)]
+fn http_request_update(
+    req: ic_certified_assets::types::HttpRequest,
+) -> ic_certified_assets::types::HttpResponse {
+    handle_api_request(&req)
+}
+
 #[update]
 #[candid_method(update)]
 async fn get_next_upgrades() -> Result<Vec<UpgradeWithTrack>, String> {
@@ -1059,6 +1898,8 @@ async fn upgrade_canister(version: String, track: String) -> Result<(), String>
 fn candid_interface_compatibility() {
     use candid_parser::utils::{service_compatible, CandidSource};
     use crate::domain::Domain;
+    use crate::search::SearchResponse;
+    use serde_bytes::ByteBuf;
     use crate::icrc7::*;
     use std::collections::HashMap;
     use icrc_ledger_types::icrc1::account::Account;
@@ -1076,3 +1917,112 @@ fn candid_interface_compatibility() {
         CandidSource::File(old_interface.as_path()),
     ).expect("The assets canister interface is not compatible with the child.did file");
 }
+
+#[test]
+fn stable_state_upgrade_compatibility() {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    use crate::icrc3::Transaction;
+    use crate::domain::Domain;
+
+    // state as saved by the first release, before the fields added since then
+    #[derive(CandidType, Deserialize)]
+    enum BaselineUserRole { Admin }
+    #[derive(CandidType, Deserialize)]
+    struct BaselineRole { timestamp: u64, role: BaselineUserRole }
+    #[derive(CandidType, Deserialize)]
+    struct BaselinePost { title: String, description: String, timestamp: u64, status: PostStatus }
+    #[derive(CandidType, Deserialize)]
+    struct BaselineReply { text: String, timestamp: u64, status: ReplyStatus }
+    #[derive(CandidType, Deserialize, Default)]
+    struct BaselineRelations {
+        profile_id_to_post_id: Relation<u64, u64>,
+        profile_id_to_reply_id: Relation<u64, u64>,
+        reply_id_to_post_id: Relation<u64, u64>,
+        profile_id_to_role_id: Relation<u64, u64>,
+        post_id_to_liked_post_id: Relation<u64, u64>,
+        profile_id_to_liked_post_id: Relation<u64, u64>,
+        reply_id_to_liked_reply_id: Relation<u64, u64>,
+        profile_id_to_liked_reply_id: Relation<u64, u64>,
+    }
+    #[derive(CandidType, Deserialize, Default)]
+    struct BaselineIndexes {
+        profile: HashMap<AuthenticationWithAddress, u64>,
+        active_principal: HashMap<Principal, u64>,
+        has_liked_post: HashMap<(u64, u64), ()>,
+        has_liked_reply: HashMap<(u64, u64), ()>,
+        most_liked_replies: HashMap<u64, BTreeSet<ValueEntry<u64, u64>>>,
+        most_liked_posts: HashMap<u64, BTreeSet<ValueEntry<u64, u64>>>,
+    }
+    #[derive(CandidType, Deserialize, Default)]
+    struct BaselineState {
+        profiles: BTreeMap<u64, Profile>,
+        posts: BTreeMap<u64, BaselinePost>,
+        replies: BTreeMap<u64, BaselineReply>,
+        roles: BTreeMap<u64, BaselineRole>,
+        liked_posts: BTreeMap<u64, LikedPost>,
+        liked_replies: BTreeMap<u64, LikedReply>,
+        relations: BaselineRelations,
+        indexes: BaselineIndexes,
+        parent: Option<Principal>,
+        version: Option<String>,
+        track: Option<String>,
+        txn_log: BTreeMap<u128, Transaction>,
+        uuid_count: u64,
+        domain: Option<Domain>
+    }
+    #[derive(CandidType, Deserialize)]
+    struct BaselineStableState {
+        state: BaselineState,
+        storage: ic_certified_assets::StableState,
+    }
+
+    let principal = Principal::anonymous();
+    let mut state = BaselineState { uuid_count: 4, ..Default::default() };
+    state.profiles.insert(0, Profile {
+        name: String::new(),
+        description: String::new(),
+        authentication: Authentication::Ic,
+        active_principal: principal,
+        timestamp: 0,
+        last_login: 0
+    });
+    state.posts.insert(1, BaselinePost { title: "title".to_owned(), description: "description".to_owned(), timestamp: 0, status: PostStatus::Visible });
+    state.replies.insert(2, BaselineReply { text: "text".to_owned(), timestamp: 0, status: ReplyStatus::Visible });
+    state.roles.insert(3, BaselineRole { timestamp: 0, role: BaselineUserRole::Admin });
+    state.relations.profile_id_to_post_id.insert(0, 1);
+    state.relations.profile_id_to_reply_id.insert(0, 2);
+    state.relations.reply_id_to_post_id.insert(2, 1);
+    state.relations.profile_id_to_role_id.insert(0, 3);
+    state.indexes.profile.insert(AuthenticationWithAddress::Ic(IcParams { principal }), 0);
+    state.indexes.active_principal.insert(principal, 0);
+
+    let baseline = BaselineStableState { state, storage: ic_certified_assets::pre_upgrade() };
+    let bytes = Encode!(&baseline).expect("The baseline state could not be encoded");
+    let restored = Decode!(&bytes, StableState).expect("The baseline state could not be restored");
+
+    // existing entities are kept and the fields added since then are empty
+    assert_eq!(restored.state.posts.get(&1).unwrap().title, "title");
+    assert_eq!(restored.state.posts.get(&1).unwrap().flags, None);
+    assert_eq!(restored.state.replies.get(&2).unwrap().text, "text");
+    assert_eq!(restored.state.roles.get(&3).unwrap().role, UserRole::Admin);
+    assert_eq!(restored.state.relations.reply_id_to_post_id.forward.get(&2).unwrap().len(), 1);
+    assert!(restored.state.indexes.handle.is_empty());
+    assert!(restored.state.post_stats.is_empty());
+    assert!(restored.state.relations.reply_id_to_parent_reply_id.forward.is_empty());
+    assert!(restored.state.indexes.posts_by_timestamp.entries.is_empty());
+    assert!(restored.state.sanctions.is_empty());
+    assert!(restored.state.indexes.search_posts.is_missing());
+    assert!(restored.state.post_stats.is_missing());
+    assert!(restored.state.indexes.pinned_posts.is_missing());
+
+    // and the new state is restored by the next upgrade, with a rebuild in progress saved as missing
+    let mut restored = restored;
+    restored.state.indexes.tags.start_rebuild();
+    restored.state.indexes.search_posts.finish_rebuild();
+    let bytes = Encode!(&restored).expect("The state could not be encoded");
+    let restored = Decode!(&bytes, StableState).expect("The state could not be restored");
+    assert_eq!(restored.state.uuid_count, 4);
+    assert!(restored.state.indexes.tags.is_missing());
+    assert!(!restored.state.indexes.search_posts.is_missing());
+    assert!(!restored.state.post_stats.is_missing());
+}
@@ -0,0 +1,229 @@
+use crate::state::State;
+use crate::seo::render_post_files;
+use crate::syndication::update_feeds;
+use crate::utils::migrate_in_batches;
+
+// renders a subset of markdown (paragraphs, headings, lists, quotes, code, emphasis and links)
+// raw html is always escaped and links are only kept for allowed schemes
+const ALLOWED_LINK_PREFIXES: [&str; 5] = ["https://", "http://", "mailto:", "/", "#"];
+const INLINE_DELIMITERS: [(&str, &str); 3] = [("**", "strong"), ("~~", "del"), ("*", "em")];
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+fn sanitize_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty() || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return None;
+    }
+    let lowercase_url = url.to_lowercase();
+    if !ALLOWED_LINK_PREFIXES.iter().any(|prefix| lowercase_url.starts_with(prefix)) || lowercase_url.starts_with("//") {
+        return None;
+    }
+    Some(escape_html(url))
+}
+
+// `[text](url)` at the start of the text, returns the text, the url and the length of the link
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let text_end = text.find("](")?;
+    let url_end = text[text_end + 2..].find(')')?;
+    let link_text = &text[1..text_end];
+    if link_text.is_empty() || link_text.contains('[') {
+        return None;
+    }
+    Some((link_text, &text[text_end + 2..text_end + 2 + url_end], text_end + 2 + url_end + 1))
+}
+
+fn render_inline(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        // escaped punctuation
+        if c == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(|next| next.is_ascii_punctuation()) {
+                html.push_str(&escape_html(&next.to_string()));
+                rest = &rest[1 + next.len_utf8()..];
+                continue;
+            }
+        }
+
+        // code span
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                html.push_str(&format!("<code>{}</code>", escape_html(&rest[1..1 + end])));
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+
+        // emphasis
+        for (delimiter, tag) in INLINE_DELIMITERS {
+            if !rest.starts_with(delimiter) {
+                continue;
+            }
+            let start = delimiter.len();
+            if let Some(end) = rest[start..].find(delimiter).filter(|end| *end > 0) {
+                html.push_str(&format!("<{}>{}</{}>", tag, render_inline(&rest[start..start + end]), tag));
+                rest = &rest[start + end + delimiter.len()..];
+                continue 'outer;
+            }
+        }
+
+        // link
+        if c == '[' {
+            if let Some((link_text, url, length)) = parse_link(rest) {
+                match sanitize_url(url) {
+                    Some(url) => html.push_str(&format!("<a href=\"{}\" rel=\"nofollow noopener\">{}</a>", url, render_inline(link_text))),
+                    None => html.push_str(&render_inline(link_text))
+                }
+                rest = &rest[length..];
+                continue;
+            }
+        }
+
+        html.push_str(&escape_html(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    html
+}
+
+fn get_heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+// returns if the list is ordered and the text of the item
+fn get_list_item(line: &str) -> Option<(bool, &str)> {
+    if ["- ", "* ", "+ "].iter().any(|marker| line.starts_with(marker)) {
+        return Some((false, &line[2..]));
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return Some((true, &line[digits + 2..]));
+    }
+    None
+}
+
+fn is_block_start(line: &str) -> bool {
+    line.starts_with("```") || line.starts_with('>') || get_heading_level(line).is_some() || get_list_item(line).is_some()
+}
+
+fn render_blocks(lines: &[&str]) -> String {
+    let mut html = String::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index].trim();
+
+        if line.is_empty() {
+            index += 1;
+        } else if line.starts_with("```") {
+            let mut code_lines = vec![];
+            index += 1;
+            while index < lines.len() && !lines[index].trim().starts_with("```") {
+                code_lines.push(lines[index]);
+                index += 1;
+            }
+            index += 1;
+            html.push_str(&format!("<pre><code>{}</code></pre>", escape_html(&code_lines.join("\n"))));
+        } else if let Some(level) = get_heading_level(line) {
+            html.push_str(&format!("<h{}>{}</h{}>", level, render_inline(line[level..].trim()), level));
+            index += 1;
+        } else if line.starts_with('>') {
+            let mut quote_lines = vec![];
+            while index < lines.len() && lines[index].trim().starts_with('>') {
+                let quote_line = lines[index].trim()[1..].to_owned();
+                quote_lines.push(quote_line.strip_prefix(' ').map(|x| x.to_owned()).unwrap_or(quote_line));
+                index += 1;
+            }
+            let quote_lines = quote_lines.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+            html.push_str(&format!("<blockquote>{}</blockquote>", render_blocks(&quote_lines)));
+        } else if let Some((ordered, _)) = get_list_item(line) {
+            let tag = if ordered { "ol" } else { "ul" };
+            html.push_str(&format!("<{}>", tag));
+            while let Some((item_ordered, item)) = lines.get(index).and_then(|x| get_list_item(x.trim())) {
+                if item_ordered != ordered {
+                    break;
+                }
+                html.push_str(&format!("<li>{}</li>", render_inline(item)));
+                index += 1;
+            }
+            html.push_str(&format!("</{}>", tag));
+        } else {
+            let mut paragraph_lines = vec![];
+            while index < lines.len() && !lines[index].trim().is_empty() && (paragraph_lines.is_empty() || !is_block_start(lines[index].trim())) {
+                paragraph_lines.push(render_inline(lines[index].trim()));
+                index += 1;
+            }
+            html.push_str(&format!("<p>{}</p>", paragraph_lines.join("<br>")));
+        }
+    }
+
+    html
+}
+
+pub fn render_markdown(source: &str) -> String {
+    render_blocks(&source.lines().collect::<Vec<_>>())
+}
+
+pub fn update_post_html(state: &mut State, post_id: u64) {
+    let post = state.posts.get_mut(&post_id).unwrap();
+    post.description_html = Some(render_markdown(&post.description));
+}
+
+pub fn update_reply_html(state: &mut State, reply_id: u64) {
+    let reply = state.replies.get_mut(&reply_id).unwrap();
+    reply.text_html = Some(render_markdown(&reply.text));
+}
+
+pub fn get_post_html(state: &State, post_id: &u64) -> String {
+    state.posts.get(post_id).unwrap().description_html.to_owned().unwrap_or_default()
+}
+
+pub fn get_reply_html(state: &State, reply_id: &u64) -> String {
+    state.replies.get(reply_id).unwrap().text_html.to_owned().unwrap_or_default()
+}
+
+fn render_missing_post_html(state: &mut State, post_id: u64) {
+    if state.posts.get(&post_id).map(|post| post.description_html.is_none()).unwrap_or(false) {
+        update_post_html(state, post_id);
+    }
+    let reply_ids = state.relations.reply_id_to_post_id.backward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for reply_id in reply_ids {
+        if state.replies.get(&reply_id).unwrap().text_html.is_none() {
+            update_reply_html(state, reply_id);
+        }
+    }
+}
+
+// posts and replies created before markdown support have no rendered html,
+// the post files are rendered again once they have it
+pub fn render_missing_html(state: &mut State) {
+    let is_missing = state.posts.values().any(|post| post.description_html.is_none()) || state.replies.values().any(|reply| reply.text_html.is_none());
+    if !is_missing {
+        return;
+    }
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    migrate_in_batches(post_ids, render_missing_post_html, |state| {
+        render_post_files(state);
+        update_feeds(state);
+    });
+}
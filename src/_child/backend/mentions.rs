@@ -0,0 +1,162 @@
+use candid::candid_method;
+use ic_cdk::query;
+
+use std::collections::BTreeSet;
+
+use crate::state::{State, STATE, AuthenticationWithAddress, EvmParams, SvmParams, Mention, MentionResponse, NotificationKind, PostSort, PostCursor, PostsPage, Permission};
+use crate::auth::get_authentication_with_address;
+use crate::names::handle_key;
+use crate::notifications::notify;
+use crate::roles::caller_has_permission;
+use crate::verify::checksum_evm_address;
+use crate::get_page;
+
+const EVM_ADDRESS_LENGTH: usize = 42;
+const MIN_SVM_ADDRESS_LENGTH: usize = 32;
+const MAX_SVM_ADDRESS_LENGTH: usize = 44;
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// `@` followed by letters, numbers and underscores, not preceded by a word character (e.g. emails)
+fn get_mention_candidates(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let is_word_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+
+    let mut candidates = vec![];
+    for (index, c) in chars.iter().enumerate() {
+        if *c != '@' || (index > 0 && is_word_char(&chars[index - 1])) {
+            continue;
+        }
+        let candidate = chars[index + 1..].iter().take_while(|c| is_word_char(c)).collect::<String>();
+        if !candidate.is_empty() {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+fn resolve_mention(state: &State, candidate: &str) -> Option<u64> {
+    let is_evm_address = candidate.len() == EVM_ADDRESS_LENGTH
+        && candidate.starts_with("0x")
+        && candidate[2..].chars().all(|c| c.is_ascii_hexdigit());
+    if is_evm_address {
+        let address = checksum_evm_address(candidate.to_lowercase());
+        return state.indexes.profile.get(&AuthenticationWithAddress::Evm(EvmParams { address })).cloned();
+    }
+
+    let is_svm_address = candidate.len() >= MIN_SVM_ADDRESS_LENGTH
+        && candidate.len() <= MAX_SVM_ADDRESS_LENGTH
+        && candidate.chars().all(|c| BASE58_ALPHABET.contains(c));
+    if is_svm_address {
+        let authentication = AuthenticationWithAddress::Svm(SvmParams { address: candidate.to_owned() });
+        if let Some(profile_id) = state.indexes.profile.get(&authentication) {
+            return Some(profile_id.to_owned());
+        }
+    }
+
+    state.indexes.handle.get(&handle_key(candidate)).cloned()
+}
+
+pub fn parse_mentions(state: &State, text: &str) -> Vec<Mention> {
+    let mut mentions: Vec<Mention> = vec![];
+    for candidate in get_mention_candidates(text) {
+        if let Some(profile_id) = resolve_mention(state, &candidate) {
+            let mention = Mention { text: format!("@{}", candidate), profile_id };
+            if !mentions.contains(&mention) {
+                mentions.push(mention);
+            }
+        }
+    }
+    mentions
+}
+
+fn get_mentioned_profile_ids(mentions: &[Mention]) -> BTreeSet<u64> {
+    mentions.iter().map(|mention| mention.profile_id).collect()
+}
+
+// stores the mentions of the post description and notifies newly mentioned profiles
+pub fn update_post_mentions(state: &mut State, post_id: u64, author_id: u64) {
+    let post = state.posts.get(&post_id).unwrap();
+    let prev_profile_ids = get_mentioned_profile_ids(&post.mentions.to_owned().unwrap_or_default());
+    let mentions = parse_mentions(state, &post.description);
+    let profile_ids = get_mentioned_profile_ids(&mentions);
+
+    state.posts.get_mut(&post_id).unwrap().mentions = Some(mentions);
+    for profile_id in prev_profile_ids.difference(&profile_ids) {
+        state.relations.post_id_to_mentioned_profile_id.remove(post_id, profile_id.to_owned());
+    }
+    for profile_id in profile_ids.difference(&prev_profile_ids) {
+        state.relations.post_id_to_mentioned_profile_id.insert(post_id, profile_id.to_owned());
+        notify(state, profile_id.to_owned(), NotificationKind::Mention, author_id, post_id, None);
+    }
+}
+
+// stores the mentions of the reply text and notifies newly mentioned profiles
+pub fn update_reply_mentions(state: &mut State, reply_id: u64, author_id: u64) {
+    let reply = state.replies.get(&reply_id).unwrap();
+    let prev_profile_ids = get_mentioned_profile_ids(&reply.mentions.to_owned().unwrap_or_default());
+    let mentions = parse_mentions(state, &reply.text);
+    let profile_ids = get_mentioned_profile_ids(&mentions);
+    let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(&reply_id).unwrap().first_key_value().unwrap();
+    let post_id = post_id.to_owned();
+
+    state.replies.get_mut(&reply_id).unwrap().mentions = Some(mentions);
+    for profile_id in prev_profile_ids.difference(&profile_ids) {
+        state.relations.reply_id_to_mentioned_profile_id.remove(reply_id, profile_id.to_owned());
+    }
+    for profile_id in profile_ids.difference(&prev_profile_ids) {
+        state.relations.reply_id_to_mentioned_profile_id.insert(reply_id, profile_id.to_owned());
+        notify(state, profile_id.to_owned(), NotificationKind::Mention, author_id, post_id, Some(reply_id));
+    }
+}
+
+fn get_mention_responses(state: &State, mentions: &Option<Vec<Mention>>) -> Vec<MentionResponse> {
+    mentions
+        .to_owned()
+        .unwrap_or_default()
+        .iter()
+        .map(|mention| {
+            let profile = state.profiles.get(&mention.profile_id).unwrap();
+            let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+            MentionResponse { text: mention.text.to_owned(), authentication }
+        })
+        .collect::<Vec<_>>()
+}
+
+pub fn get_post_mentions(state: &State, post_id: &u64) -> Vec<MentionResponse> {
+    get_mention_responses(state, &state.posts.get(post_id).unwrap().mentions)
+}
+
+pub fn get_reply_mentions(state: &State, reply_id: &u64) -> Vec<MentionResponse> {
+    get_mention_responses(state, &state.replies.get(reply_id).unwrap().mentions)
+}
+
+// posts that mention the caller in their description or in one of their replies
+#[query]
+#[candid_method(query)]
+fn get_mentioned_posts_page(sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> Result<PostsPage, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let mut post_ids = state.relations.post_id_to_mentioned_profile_id.backward
+            .get(profile_id)
+            .map(|post_ids| post_ids.keys().cloned().collect::<BTreeSet<_>>())
+            .unwrap_or_default();
+        if let Some(reply_ids) = state.relations.reply_id_to_mentioned_profile_id.backward.get(profile_id) {
+            for reply_id in reply_ids.keys() {
+                let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(reply_id).unwrap().first_key_value().unwrap();
+                post_ids.insert(post_id.to_owned());
+            }
+        }
+
+        Ok(get_page(&state, sort, cursor_opt, limit_opt, caller_can_hide_content, |post_id| post_ids.contains(post_id)))
+    })
+}
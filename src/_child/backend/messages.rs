@@ -0,0 +1,303 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+
+use std::collections::BTreeMap;
+
+use crate::state::{State, STATE, AuthenticationWithAddress, Conversation, ConversationResponse, Message, MessageResponse, MessagesPage, Permission};
+use crate::auth::get_authentication_with_address;
+use crate::utils::uuid;
+use crate::roles::caller_has_permission;
+
+const MAX_MESSAGE_LENGTH: usize = 2000;
+const DEFAULT_MESSAGES_LIMIT: u64 = 50;
+const MAX_MESSAGES_LIMIT: u64 = 100;
+
+fn conversation_key(profile_id: u64, other_profile_id: u64) -> (u64, u64) {
+    (profile_id.min(other_profile_id), profile_id.max(other_profile_id))
+}
+
+fn get_profile_authentication(state: &State, profile_id: &u64) -> AuthenticationWithAddress {
+    let profile = state.profiles.get(profile_id).unwrap();
+    get_authentication_with_address(&profile.authentication, &profile.active_principal)
+}
+
+fn get_message_response(state: &State, message_id: &u64) -> MessageResponse {
+    let message = state.messages.get(message_id).unwrap();
+    let (profile_id, _) = state.relations.profile_id_to_message_id.backward.get(message_id).unwrap().first_key_value().unwrap();
+    MessageResponse {
+        message_id: message_id.to_owned(),
+        text: message.text.to_owned(),
+        timestamp: message.timestamp,
+        sender: get_profile_authentication(state, profile_id)
+    }
+}
+
+// message ids of a conversation from newest to oldest
+fn get_message_ids(state: &State, conversation_id: &u64) -> Vec<u64> {
+    let mut message_ids = state.relations.conversation_id_to_message_id.forward
+        .get(conversation_id)
+        .map(|message_ids| message_ids.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    message_ids.sort_by_key(|message_id| (state.messages.get(message_id).unwrap().timestamp, message_id.to_owned()));
+    message_ids.reverse();
+    message_ids
+}
+
+fn get_participant_id(state: &State, conversation_id: &u64, profile_id: &u64) -> Option<u64> {
+    let profile_ids = state.relations.profile_id_to_conversation_id.backward.get(conversation_id)?;
+    if !profile_ids.contains_key(profile_id) {
+        return None;
+    }
+    profile_ids.keys().find(|id| *id != profile_id).cloned()
+}
+
+#[update]
+#[candid_method(update)]
+fn send_message(recipient: AuthenticationWithAddress, text: String) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    if text.trim().is_empty() {
+        return Err("Message cannot be empty".to_owned());
+    }
+    if text.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(format!("Message should be at most {} characters", MAX_MESSAGE_LENGTH));
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let recipient_id_opt = state.indexes.profile.get(&recipient);
+        if recipient_id_opt.is_none() {
+            return Err("Recipient does not exist".to_owned());
+        }
+        let recipient_id = recipient_id_opt.cloned().unwrap();
+        if recipient_id == profile_id {
+            return Err("Cannot message yourself".to_owned());
+        }
+
+        let is_blocked = state.relations.profile_id_to_blocked_profile_id.forward
+            .get(&recipient_id)
+            .map(|blocked_profile_ids| blocked_profile_ids.contains_key(&profile_id))
+            .unwrap_or(false);
+        if is_blocked {
+            return Err("Recipient does not accept messages from caller".to_owned());
+        }
+
+        let timestamp = ic_cdk::api::time();
+        let key = conversation_key(profile_id, recipient_id);
+        let conversation_id = match state.indexes.conversation.get(&key).cloned() {
+            Some(conversation_id) => conversation_id,
+            None => {
+                let conversation_id = uuid(&mut state);
+                let conversation = Conversation { timestamp, last_message_at: timestamp, unread: BTreeMap::new() };
+                state.conversations.insert(conversation_id, conversation);
+                state.relations.profile_id_to_conversation_id.insert(profile_id, conversation_id);
+                state.relations.profile_id_to_conversation_id.insert(recipient_id, conversation_id);
+                state.indexes.conversation.insert(key, conversation_id);
+                conversation_id
+            }
+        };
+
+        let message_id = uuid(&mut state);
+        state.messages.insert(message_id, Message { text, timestamp });
+        state.relations.conversation_id_to_message_id.insert(conversation_id, message_id);
+        state.relations.profile_id_to_message_id.insert(profile_id, message_id);
+
+        let conversation = state.conversations.get_mut(&conversation_id).unwrap();
+        conversation.last_message_at = timestamp;
+        *conversation.unread.entry(recipient_id).or_insert(0) += 1;
+
+        Ok(message_id)
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_conversations() -> Result<Vec<ConversationResponse>, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let mut conversations = state.relations.profile_id_to_conversation_id.forward
+            .get(profile_id)
+            .cloned()
+            .unwrap_or_default()
+            .keys()
+            .map(|conversation_id| {
+                let conversation = state.conversations.get(conversation_id).unwrap();
+                let participant_id = get_participant_id(&state, conversation_id, profile_id).unwrap();
+                let last_message = get_message_ids(&state, conversation_id).first().map(|message_id| get_message_response(&state, message_id));
+                ConversationResponse {
+                    conversation_id: conversation_id.to_owned(),
+                    participant: get_profile_authentication(&state, &participant_id),
+                    last_message,
+                    last_message_at: conversation.last_message_at,
+                    unread_count: conversation.unread.get(profile_id).cloned().unwrap_or(0)
+                }
+            })
+            .collect::<Vec<_>>();
+        conversations.sort_by_key(|conversation| std::cmp::Reverse(conversation.last_message_at));
+
+        Ok(conversations)
+    })
+}
+
+// messages from newest to oldest, the cursor is the last message id of the previous page
+#[query]
+#[candid_method(query)]
+fn get_messages(conversation_id: u64, cursor_opt: Option<u64>, limit_opt: Option<u64>) -> Result<MessagesPage, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+        if get_participant_id(&state, &conversation_id, profile_id).is_none() {
+            return Err("Conversation does not exist".to_owned());
+        }
+
+        let message_ids = get_message_ids(&state, &conversation_id);
+        let start = match cursor_opt {
+            Some(cursor) => match message_ids.iter().position(|message_id| message_id == &cursor) {
+                Some(index) => index + 1,
+                None => return Err("Invalid cursor".to_owned())
+            },
+            None => 0
+        };
+        let limit = limit_opt.unwrap_or(DEFAULT_MESSAGES_LIMIT).min(MAX_MESSAGES_LIMIT) as usize;
+
+        let messages = message_ids
+            .iter()
+            .skip(start)
+            .take(limit)
+            .map(|message_id| get_message_response(&state, message_id))
+            .collect::<Vec<_>>();
+        let next_cursor = if start + limit < message_ids.len() { messages.last().map(|message| message.message_id) } else { None };
+
+        Ok(MessagesPage { messages, next_cursor })
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn mark_conversation_read(conversation_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+        if get_participant_id(&state, &conversation_id, &profile_id).is_none() {
+            return Err("Conversation does not exist".to_owned());
+        }
+
+        let conversation = state.conversations.get_mut(&conversation_id).unwrap();
+        conversation.unread.remove(&profile_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn block_profile(authentication: AuthenticationWithAddress) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let blocked_profile_id_opt = state.indexes.profile.get(&authentication);
+        if blocked_profile_id_opt.is_none() {
+            return Err("Profile to block does not exist".to_owned());
+        }
+        let blocked_profile_id = blocked_profile_id_opt.cloned().unwrap();
+        if blocked_profile_id == profile_id {
+            return Err("Cannot block yourself".to_owned());
+        }
+
+        state.relations.profile_id_to_blocked_profile_id.insert(profile_id, blocked_profile_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn unblock_profile(authentication: AuthenticationWithAddress) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let blocked_profile_id_opt = state.indexes.profile.get(&authentication).cloned();
+        let is_blocked = blocked_profile_id_opt
+            .and_then(|blocked_profile_id| state.relations.profile_id_to_blocked_profile_id.forward.get(&profile_id).map(|x| x.contains_key(&blocked_profile_id)))
+            .unwrap_or(false);
+        if !is_blocked {
+            return Err("Profile is not blocked".to_owned());
+        }
+
+        state.relations.profile_id_to_blocked_profile_id.remove(profile_id, blocked_profile_id_opt.unwrap());
+
+        Ok(())
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_blocked_profiles() -> Result<Vec<AuthenticationWithAddress>, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let blocked_profiles = state.relations.profile_id_to_blocked_profile_id.forward
+            .get(profile_id)
+            .map(|blocked_profile_ids| blocked_profile_ids.keys().map(|blocked_profile_id| get_profile_authentication(&state, blocked_profile_id)).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        Ok(blocked_profiles)
+    })
+}
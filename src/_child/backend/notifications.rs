@@ -0,0 +1,138 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+
+use crate::state::{State, STATE, Notification, NotificationKind, NotificationResponse, NotificationsPage};
+use crate::auth::get_authentication_with_address;
+use crate::utils::uuid;
+
+// oldest notifications are dropped when an inbox is full
+const MAX_NOTIFICATIONS: usize = 100;
+const DEFAULT_NOTIFICATIONS_LIMIT: u64 = 20;
+const MAX_NOTIFICATIONS_LIMIT: u64 = 100;
+
+pub fn notify(state: &mut State, profile_id: u64, kind: NotificationKind, actor_id: u64, post_id: u64, reply_id: Option<u64>) {
+    if profile_id == actor_id {
+        return;
+    }
+
+    let notification = Notification {
+        notification_id: uuid(state),
+        kind,
+        actor_id,
+        post_id,
+        reply_id,
+        timestamp: ic_cdk::api::time(),
+        read: false
+    };
+
+    let inbox = state.inboxes.entry(profile_id).or_default();
+    inbox.push(notification);
+    if inbox.len() > MAX_NOTIFICATIONS {
+        inbox.drain(..inbox.len() - MAX_NOTIFICATIONS);
+    }
+}
+
+fn get_notification_response(state: &State, notification: &Notification) -> NotificationResponse {
+    let profile = state.profiles.get(&notification.actor_id).unwrap();
+    NotificationResponse {
+        notification_id: notification.notification_id,
+        kind: notification.kind.to_owned(),
+        actor: get_authentication_with_address(&profile.authentication, &profile.active_principal),
+        post_id: notification.post_id,
+        reply_id: notification.reply_id,
+        timestamp: notification.timestamp,
+        read: notification.read
+    }
+}
+
+// notifications from newest to oldest, the cursor is the last notification id of the previous page
+#[query]
+#[candid_method(query)]
+fn get_notifications(cursor_opt: Option<u64>, limit_opt: Option<u64>) -> Result<NotificationsPage, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let inbox = state.inboxes.get(profile_id).cloned().unwrap_or_default();
+        let start = match cursor_opt {
+            Some(cursor) => match inbox.iter().rev().position(|notification| notification.notification_id == cursor) {
+                Some(index) => index + 1,
+                None => return Err("Invalid cursor".to_owned())
+            },
+            None => 0
+        };
+        let limit = limit_opt.unwrap_or(DEFAULT_NOTIFICATIONS_LIMIT).min(MAX_NOTIFICATIONS_LIMIT) as usize;
+
+        let notifications = inbox
+            .iter()
+            .rev()
+            .skip(start)
+            .take(limit)
+            .map(|notification| get_notification_response(&state, notification))
+            .collect::<Vec<_>>();
+        let next_cursor = if start + limit < inbox.len() { notifications.last().map(|notification| notification.notification_id) } else { None };
+
+        Ok(NotificationsPage { notifications, next_cursor })
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_unread_notifications_count() -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.unwrap();
+
+        let unread_count = state.inboxes
+            .get(profile_id)
+            .map(|inbox| inbox.iter().filter(|notification| !notification.read).count())
+            .unwrap_or(0);
+
+        Ok(unread_count as u64)
+    })
+}
+
+// marks the given notifications as read or all of them when no ids are given
+#[update]
+#[candid_method(update)]
+fn mark_notifications_read(notification_ids_opt: Option<Vec<u64>>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        if let Some(inbox) = state.inboxes.get_mut(&profile_id) {
+            for notification in inbox.iter_mut() {
+                let is_selected = match &notification_ids_opt {
+                    Some(notification_ids) => notification_ids.contains(&notification.notification_id),
+                    None => true
+                };
+                if is_selected {
+                    notification.read = true;
+                }
+            }
+        }
+
+        Ok(())
+    })
+}
@@ -0,0 +1,151 @@
+use candid::{candid_method, Principal};
+use ic_cdk::update;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::state::{State, STATE, Poll, PollResponse, PollOptionResponse, PostStatus, PostSummary, Permission};
+use crate::create_post;
+use crate::roles::caller_has_permission;
+
+const MIN_POLL_OPTIONS: usize = 2;
+const MAX_POLL_OPTIONS: usize = 10;
+const MAX_POLL_OPTION_LENGTH: usize = 100;
+
+fn close_poll(post_id: u64) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if let Some(poll) = state.polls.get_mut(&post_id) {
+            poll.closed = true;
+        }
+    });
+}
+
+fn set_poll_timer(post_id: u64, closes_at: u64) {
+    let delay = Duration::from_nanos(closes_at.saturating_sub(ic_cdk::api::time()));
+    ic_cdk_timers::set_timer(delay, move || close_poll(post_id));
+}
+
+// timers do not survive upgrades so they are set again for open polls
+pub fn set_poll_timers(state: &State) {
+    for (post_id, poll) in state.polls.iter().filter(|(_, poll)| !poll.closed) {
+        set_poll_timer(post_id.to_owned(), poll.closes_at);
+    }
+}
+
+pub fn get_poll_response(state: &State, post_id: &u64, caller: &Principal) -> Option<PollResponse> {
+    let poll = state.polls.get(post_id)?;
+
+    let mut votes_count = vec![0; poll.options.len()];
+    for option_ids in poll.votes.values() {
+        for option_id in option_ids {
+            votes_count[*option_id as usize] += 1;
+        }
+    }
+    let options = poll.options
+        .iter()
+        .zip(votes_count)
+        .map(|(text, votes)| PollOptionResponse { text: text.to_owned(), votes })
+        .collect::<Vec<_>>();
+
+    let caller_votes = state.indexes.active_principal
+        .get(caller)
+        .and_then(|profile_id| poll.votes.get(profile_id))
+        .cloned()
+        .unwrap_or_default();
+
+    Some(PollResponse {
+        options,
+        closes_at: poll.closes_at,
+        multi_choice: poll.multi_choice,
+        closed: poll.closed || poll.closes_at <= ic_cdk::api::time(),
+        voters: poll.votes.len() as u64,
+        caller_votes
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn create_poll(title: String, description: String, options: Vec<String>, closes_at: u64, multi_choice: bool) -> Result<PostSummary, String> {
+    if options.len() < MIN_POLL_OPTIONS || options.len() > MAX_POLL_OPTIONS {
+        return Err(format!("Poll should have between {} and {} options", MIN_POLL_OPTIONS, MAX_POLL_OPTIONS));
+    }
+    let options = options.iter().map(|option| option.trim().to_owned()).collect::<Vec<_>>();
+    if options.iter().any(|option| option.is_empty() || option.chars().count() > MAX_POLL_OPTION_LENGTH) {
+        return Err(format!("Poll options should be between 1 and {} characters", MAX_POLL_OPTION_LENGTH));
+    }
+    if options.iter().enumerate().any(|(index, option)| options[..index].contains(option)) {
+        return Err("Poll options should be unique".to_owned());
+    }
+    if closes_at <= ic_cdk::api::time() {
+        return Err("Poll should close in the future".to_owned());
+    }
+
+    let post = create_post(title, description, None, None)?;
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let poll = Poll {
+            options,
+            closes_at,
+            multi_choice,
+            closed: false,
+            votes: BTreeMap::new()
+        };
+        state.polls.insert(post.post_id, poll);
+    });
+    set_poll_timer(post.post_id, closes_at);
+
+    Ok(post)
+}
+
+#[update]
+#[candid_method(update)]
+fn vote(post_id: u64, option_ids: Vec<u64>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let post_opt = state.posts.get(&post_id);
+        if post_opt.is_none() || post_opt.unwrap().status == PostStatus::Hidden {
+            return Err("Post does not exist".to_owned());
+        }
+
+        let poll_opt = state.polls.get_mut(&post_id);
+        if poll_opt.is_none() {
+            return Err("Post is not a poll".to_owned());
+        }
+        let poll = poll_opt.unwrap();
+
+        if poll.closed || poll.closes_at <= ic_cdk::api::time() {
+            return Err("Poll is closed".to_owned());
+        }
+        if poll.votes.contains_key(&profile_id) {
+            return Err("Already voted".to_owned());
+        }
+
+        let mut option_ids = option_ids;
+        option_ids.sort();
+        option_ids.dedup();
+        if option_ids.is_empty() || (!poll.multi_choice && option_ids.len() > 1) {
+            return Err("Invalid number of options".to_owned());
+        }
+        if option_ids.iter().any(|option_id| *option_id as usize >= poll.options.len()) {
+            return Err("Option does not exist".to_owned());
+        }
+
+        poll.votes.insert(profile_id, option_ids);
+
+        Ok(())
+    })
+}
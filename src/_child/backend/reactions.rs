@@ -0,0 +1,267 @@
+use candid::{candid_method, Principal};
+use ic_cdk::update;
+
+use std::collections::BTreeMap;
+
+use crate::state::{State, STATE, Reaction, ReactionKind, ReactionCount, AuthenticationWithAddress, ValueEntry, NotificationKind, Permission};
+use crate::auth::get_authentication_with_address;
+use crate::utils::uuid;
+use crate::roles::caller_has_permission;
+use crate::notifications::notify;
+use crate::update_post_indexes;
+
+pub const REACTION_KINDS: [ReactionKind; 6] = [
+    ReactionKind::Like,
+    ReactionKind::Love,
+    ReactionKind::Laugh,
+    ReactionKind::Wow,
+    ReactionKind::Sad,
+    ReactionKind::Angry,
+];
+
+fn count_reactions(state: &State, reaction_ids_opt: Option<&BTreeMap<u64, ()>>, kind: &ReactionKind) -> u64 {
+    reaction_ids_opt
+        .map(|reaction_ids| reaction_ids.keys().filter(|reaction_id| &state.reactions.get(reaction_id).unwrap().kind == kind).count())
+        .unwrap_or(0) as u64
+}
+
+// like reactions with the profile that reacted, same shape as the former likes
+fn get_likes(state: &State, reaction_ids_opt: Option<&BTreeMap<u64, ()>>) -> Vec<(u64, AuthenticationWithAddress)> {
+    let reaction_ids = reaction_ids_opt.cloned().unwrap_or_default();
+    reaction_ids
+        .keys()
+        .filter(|reaction_id| state.reactions.get(reaction_id).unwrap().kind == ReactionKind::Like)
+        .map(|reaction_id| {
+            let (profile_id, _) = state.relations.profile_id_to_reaction_id.backward.get(reaction_id).unwrap().first_key_value().unwrap();
+            let profile = state.profiles.get(profile_id).unwrap();
+            let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+            (reaction_id.to_owned(), authentication)
+        })
+        .collect::<Vec<_>>()
+}
+
+fn get_reaction_counts(state: &State, reaction_ids_opt: Option<&BTreeMap<u64, ()>>, target_id: &u64, caller: &Principal) -> Vec<ReactionCount> {
+    let caller_profile_id_opt = state.indexes.active_principal.get(caller);
+    REACTION_KINDS
+        .iter()
+        .map(|kind| {
+            let caller_reacted = caller_profile_id_opt
+                .map(|profile_id| state.indexes.has_reacted.contains_key(&(profile_id.to_owned(), target_id.to_owned(), kind.to_owned())))
+                .unwrap_or(false);
+            ReactionCount { kind: kind.to_owned(), count: count_reactions(state, reaction_ids_opt, kind), caller_reacted }
+        })
+        .filter(|reaction_count| reaction_count.count > 0)
+        .collect::<Vec<_>>()
+}
+
+pub fn get_post_likes(state: &State, post_id: &u64) -> Vec<(u64, AuthenticationWithAddress)> {
+    get_likes(state, state.relations.post_id_to_reaction_id.forward.get(post_id))
+}
+
+pub fn get_reply_likes(state: &State, reply_id: &u64) -> Vec<(u64, AuthenticationWithAddress)> {
+    get_likes(state, state.relations.reply_id_to_reaction_id.forward.get(reply_id))
+}
+
+pub fn get_post_likes_count(state: &State, post_id: &u64) -> u64 {
+    count_reactions(state, state.relations.post_id_to_reaction_id.forward.get(post_id), &ReactionKind::Like)
+}
+
+pub fn get_profile_likes_count(state: &State, profile_id: &u64) -> u64 {
+    count_reactions(state, state.relations.profile_id_to_reaction_id.forward.get(profile_id), &ReactionKind::Like)
+}
+
+pub fn get_post_reactions(state: &State, post_id: &u64, caller: &Principal) -> Vec<ReactionCount> {
+    get_reaction_counts(state, state.relations.post_id_to_reaction_id.forward.get(post_id), post_id, caller)
+}
+
+pub fn get_reply_reactions(state: &State, reply_id: &u64, caller: &Principal) -> Vec<ReactionCount> {
+    get_reaction_counts(state, state.relations.reply_id_to_reaction_id.forward.get(reply_id), reply_id, caller)
+}
+
+pub fn get_reaction_id(state: &State, profile_id: &u64, target_id: &u64, kind: &ReactionKind) -> Option<u64> {
+    state.indexes.has_reacted.get(&(profile_id.to_owned(), target_id.to_owned(), kind.to_owned())).cloned()
+}
+
+fn update_most_liked_posts(state: &mut State, post_id: u64) {
+    let (profile_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+    let profile_id = profile_id.to_owned();
+    let post_likes = get_post_likes_count(state, &post_id);
+    let most_liked_posts = state.indexes.most_liked_posts.entry(profile_id).or_default();
+    most_liked_posts.retain(|a| a.get().0 != &post_id);
+    if post_likes > 0 {
+        most_liked_posts.insert(ValueEntry::new(post_id, post_likes));
+    }
+}
+
+fn update_most_liked_replies(state: &mut State, reply_id: u64) {
+    let (profile_id, _) = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().first_key_value().unwrap();
+    let profile_id = profile_id.to_owned();
+    let reply_likes = count_reactions(state, state.relations.reply_id_to_reaction_id.forward.get(&reply_id), &ReactionKind::Like);
+    let most_liked_replies = state.indexes.most_liked_replies.entry(profile_id).or_default();
+    most_liked_replies.retain(|a| a.get().0 != &reply_id);
+    if reply_likes > 0 {
+        most_liked_replies.insert(ValueEntry::new(reply_id, reply_likes));
+    }
+}
+
+fn insert_reaction(state: &mut State, reaction_id: u64, profile_id: u64, target_id: u64, reaction: Reaction) {
+    state.indexes.has_reacted.insert((profile_id, target_id, reaction.kind.to_owned()), reaction_id);
+    state.relations.profile_id_to_reaction_id.insert(profile_id, reaction_id);
+    state.reactions.insert(reaction_id, reaction);
+}
+
+pub fn add_post_reaction(state: &mut State, reaction_id: u64, profile_id: u64, post_id: u64, kind: ReactionKind) {
+    let reaction = Reaction { kind: kind.to_owned(), timestamp: ic_cdk::api::time() };
+    insert_reaction(state, reaction_id, profile_id, post_id, reaction);
+    state.relations.post_id_to_reaction_id.insert(post_id, reaction_id);
+
+    if kind == ReactionKind::Like {
+        state.post_stats.get_mut(&post_id).unwrap().likes_count += 1;
+        update_post_indexes(state, post_id);
+        update_most_liked_posts(state, post_id);
+
+        let (author_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+        notify(state, author_id.to_owned(), NotificationKind::PostLike, profile_id, post_id, None);
+    }
+}
+
+pub fn add_reply_reaction(state: &mut State, reaction_id: u64, profile_id: u64, reply_id: u64, kind: ReactionKind) {
+    let reaction = Reaction { kind: kind.to_owned(), timestamp: ic_cdk::api::time() };
+    insert_reaction(state, reaction_id, profile_id, reply_id, reaction);
+    state.relations.reply_id_to_reaction_id.insert(reply_id, reaction_id);
+
+    if kind == ReactionKind::Like {
+        update_most_liked_replies(state, reply_id);
+
+        let (author_id, _) = state.relations.profile_id_to_reply_id.backward.get(&reply_id).unwrap().first_key_value().unwrap();
+        let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(&reply_id).unwrap().first_key_value().unwrap();
+        notify(state, author_id.to_owned(), NotificationKind::ReplyLike, profile_id, post_id.to_owned(), Some(reply_id));
+    }
+}
+
+pub fn remove_reaction(state: &mut State, reaction_id: u64) {
+    let reaction = state.reactions.remove(&reaction_id).unwrap();
+    let (profile_id, _) = state.relations.profile_id_to_reaction_id.backward.get(&reaction_id).unwrap().first_key_value().unwrap();
+    let profile_id = profile_id.to_owned();
+    state.relations.profile_id_to_reaction_id.remove(profile_id, reaction_id);
+
+    let post_id_opt = state.relations.post_id_to_reaction_id.backward.get(&reaction_id).and_then(|x| x.first_key_value()).map(|(post_id, _)| post_id.to_owned());
+    if let Some(post_id) = post_id_opt {
+        state.relations.post_id_to_reaction_id.remove(post_id, reaction_id);
+        state.indexes.has_reacted.remove(&(profile_id, post_id, reaction.kind.to_owned()));
+        if reaction.kind == ReactionKind::Like {
+            state.post_stats.get_mut(&post_id).unwrap().likes_count -= 1;
+            update_post_indexes(state, post_id);
+            update_most_liked_posts(state, post_id);
+        }
+        return;
+    }
+
+    let (reply_id, _) = state.relations.reply_id_to_reaction_id.backward.get(&reaction_id).unwrap().first_key_value().unwrap();
+    let reply_id = reply_id.to_owned();
+    state.relations.reply_id_to_reaction_id.remove(reply_id, reaction_id);
+    state.indexes.has_reacted.remove(&(profile_id, reply_id, reaction.kind.to_owned()));
+    if reaction.kind == ReactionKind::Like {
+        update_most_liked_replies(state, reply_id);
+    }
+}
+
+// moves the former likes to like reactions keeping their ids, once for the state of a version without reactions
+pub fn migrate_likes(state: &mut State) {
+    if !state.reactions.is_missing() {
+        return;
+    }
+    for (liked_post_id, liked_post) in std::mem::take(&mut state.liked_posts) {
+        let (post_id, _) = state.relations.post_id_to_liked_post_id.backward.get(&liked_post_id).unwrap().first_key_value().unwrap();
+        let (profile_id, _) = state.relations.profile_id_to_liked_post_id.backward.get(&liked_post_id).unwrap().first_key_value().unwrap();
+        let (post_id, profile_id) = (post_id.to_owned(), profile_id.to_owned());
+        let reaction = Reaction { kind: ReactionKind::Like, timestamp: liked_post.timestamp };
+        insert_reaction(state, liked_post_id, profile_id, post_id, reaction);
+        state.relations.post_id_to_reaction_id.insert(post_id, liked_post_id);
+    }
+    for (liked_reply_id, liked_reply) in std::mem::take(&mut state.liked_replies) {
+        let (reply_id, _) = state.relations.reply_id_to_liked_reply_id.backward.get(&liked_reply_id).unwrap().first_key_value().unwrap();
+        let (profile_id, _) = state.relations.profile_id_to_liked_reply_id.backward.get(&liked_reply_id).unwrap().first_key_value().unwrap();
+        let (reply_id, profile_id) = (reply_id.to_owned(), profile_id.to_owned());
+        let reaction = Reaction { kind: ReactionKind::Like, timestamp: liked_reply.timestamp };
+        insert_reaction(state, liked_reply_id, profile_id, reply_id, reaction);
+        state.relations.reply_id_to_reaction_id.insert(reply_id, liked_reply_id);
+    }
+
+    state.relations.post_id_to_liked_post_id.clear();
+    state.relations.profile_id_to_liked_post_id.clear();
+    state.relations.reply_id_to_liked_reply_id.clear();
+    state.relations.profile_id_to_liked_reply_id.clear();
+    state.indexes.has_liked_post.clear();
+    state.indexes.has_liked_reply.clear();
+}
+
+// adds the reaction or removes it when the caller already reacted with the same kind
+#[update]
+#[candid_method(update)]
+fn react_to_post(post_id: u64, kind: ReactionKind) -> Result<bool, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exist".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+        if !state.posts.contains_key(&post_id) {
+            return Err("Post does not exist".to_owned());
+        }
+
+        match get_reaction_id(&state, &profile_id, &post_id, &kind) {
+            Some(reaction_id) => {
+                remove_reaction(&mut state, reaction_id);
+                Ok(false)
+            },
+            None => {
+                let reaction_id = uuid(&mut state);
+                add_post_reaction(&mut state, reaction_id, profile_id, post_id, kind);
+                Ok(true)
+            }
+        }
+    })
+}
+
+// adds the reaction or removes it when the caller already reacted with the same kind
+#[update]
+#[candid_method(update)]
+fn react_to_reply(reply_id: u64, kind: ReactionKind) -> Result<bool, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::CreateContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exist".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+        if !state.replies.contains_key(&reply_id) {
+            return Err("Reply does not exist".to_owned());
+        }
+
+        match get_reaction_id(&state, &profile_id, &reply_id, &kind) {
+            Some(reaction_id) => {
+                remove_reaction(&mut state, reaction_id);
+                Ok(false)
+            },
+            None => {
+                let reaction_id = uuid(&mut state);
+                add_reply_reaction(&mut state, reaction_id, profile_id, reply_id, kind);
+                Ok(true)
+            }
+        }
+    })
+}
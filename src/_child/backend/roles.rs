@@ -0,0 +1,158 @@
+use candid::{candid_method, Principal};
+use ic_cdk::{update, query};
+
+use crate::state::{State, STATE, AuthenticationWithAddress, Permission, Role, UserRole};
+use crate::utils::uuid;
+use crate::auth::get_authentication_with_address;
+use crate::sanctions::get_sanction_roles;
+
+pub fn get_role_permissions(role: &UserRole) -> Vec<Permission> {
+    match role {
+        UserRole::Admin => vec![
+            Permission::CreateContent,
+            Permission::HideContent,
+            Permission::PinPosts,
+            Permission::ManageCategories,
+            Permission::ManageRoles,
+            Permission::ManageCommunity
+        ],
+        UserRole::Moderator => vec![Permission::CreateContent, Permission::HideContent, Permission::PinPosts],
+        UserRole::Member => vec![Permission::CreateContent],
+        UserRole::Muted | UserRole::Banned => vec![]
+    }
+}
+
+// profiles without roles can create content, muted profiles lose it and banned profiles lose every permission
+pub fn has_permission(roles: &[UserRole], permission: Permission) -> bool {
+    if roles.contains(&UserRole::Banned) {
+        return false;
+    }
+    if permission == Permission::CreateContent {
+        return !roles.contains(&UserRole::Muted);
+    }
+    roles.iter().any(|role| get_role_permissions(role).contains(&permission))
+}
+
+// callers without a profile have the permissions of a profile without roles
+fn get_caller_roles(caller: &Principal) -> Vec<UserRole> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        state.indexes.active_principal
+            .get(caller)
+            .map(|profile_id| get_profile_roles(&state, profile_id))
+            .unwrap_or_default()
+    })
+}
+
+pub fn caller_has_permission(caller: &Principal, permission: Permission) -> bool {
+    has_permission(&get_caller_roles(caller), permission)
+}
+
+// assigned roles and the roles of active sanctions on the profile address
+pub fn get_profile_roles(state: &State, profile_id: &u64) -> Vec<UserRole> {
+    let mut roles = state.relations.profile_id_to_role_id.forward
+        .get(profile_id)
+        .map(|role_ids| role_ids.keys().map(|role_id| state.roles.get(role_id).unwrap().role.to_owned()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let profile = state.profiles.get(profile_id).unwrap();
+    let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+    roles.extend(get_sanction_roles(state, &authentication));
+    roles
+}
+
+pub fn profile_has_permission(state: &State, profile_id: &u64, permission: Permission) -> bool {
+    has_permission(&get_profile_roles(state, profile_id), permission)
+}
+
+fn get_profile_role_id(state: &State, profile_id: &u64, role: &UserRole) -> Option<u64> {
+    state.relations.profile_id_to_role_id.forward
+        .get(profile_id)
+        .and_then(|role_ids| role_ids.keys().find(|role_id| &state.roles.get(role_id).unwrap().role == role).cloned())
+}
+
+// admin roles are icrc7 tokens so they are minted and burned instead
+#[update]
+#[candid_method(update)]
+fn assign_role(authentication: AuthenticationWithAddress, role: UserRole) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::ManageRoles) {
+        return Err("Caller does not have permission".to_owned());
+    }
+    if role == UserRole::Admin {
+        return Err("Admin role is managed with tokens".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.profile.get(&authentication);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exist".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        if get_profile_role_id(&state, &profile_id, &role).is_some() {
+            return Err("Profile already has the role".to_owned());
+        }
+
+        let role_id = uuid(&mut state);
+        state.roles.insert(role_id, Role { timestamp: ic_cdk::api::time(), role });
+        state.relations.profile_id_to_role_id.insert(profile_id, role_id);
+
+        Ok(())
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn revoke_role(authentication: AuthenticationWithAddress, role: UserRole) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::ManageRoles) {
+        return Err("Caller does not have permission".to_owned());
+    }
+    if role == UserRole::Admin {
+        return Err("Admin role is managed with tokens".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let profile_id_opt = state.indexes.profile.get(&authentication);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exist".to_owned());
+        }
+        let profile_id = profile_id_opt.cloned().unwrap();
+
+        let role_id_opt = get_profile_role_id(&state, &profile_id, &role);
+        if role_id_opt.is_none() {
+            return Err("Profile does not have the role".to_owned());
+        }
+        let role_id = role_id_opt.unwrap();
+
+        state.roles.remove(&role_id);
+        state.relations.profile_id_to_role_id.remove(profile_id, role_id);
+
+        Ok(())
+    })
+}
+
+// permissions of the caller so the frontend can show the matching actions
+#[query]
+#[candid_method(query)]
+fn get_permissions() -> Vec<Permission> {
+    let caller = ic_cdk::caller();
+    let roles = get_caller_roles(&caller);
+
+    [
+        Permission::CreateContent,
+        Permission::HideContent,
+        Permission::PinPosts,
+        Permission::ManageCategories,
+        Permission::ManageRoles,
+        Permission::ManageCommunity
+    ]
+        .into_iter()
+        .filter(|permission| has_permission(&roles, permission.to_owned()))
+        .collect()
+}
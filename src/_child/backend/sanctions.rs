@@ -0,0 +1,198 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+
+use crate::state::{State, STATE, AuthenticationWithAddress, EvmParams, Permission, Sanction, SanctionKind, SanctionResponse, UserRole};
+use crate::auth::get_authentication_with_address;
+use crate::roles::{caller_has_permission, profile_has_permission};
+use crate::utils::uuid;
+use crate::verify::checksum_evm_address;
+
+const MAX_REASON_LENGTH: usize = 500;
+
+// sanctions without expiry are permanent, expired ones are kept as history
+fn is_active(sanction: &Sanction, now: u64) -> bool {
+    sanction.expires_at.map(|expires_at| expires_at > now).unwrap_or(true)
+}
+
+// profiles store checksummed evm addresses so any casing of the address is sanctioned
+fn normalize_authentication(authentication: AuthenticationWithAddress) -> AuthenticationWithAddress {
+    match authentication {
+        AuthenticationWithAddress::Evm(params) => AuthenticationWithAddress::Evm(EvmParams { address: checksum_evm_address(params.address.to_lowercase()) }),
+        authentication => authentication
+    }
+}
+
+fn get_sanction_ids(state: &State, authentication: &AuthenticationWithAddress) -> Vec<u64> {
+    state.indexes.sanctions.get(authentication).map(|x| x.iter().cloned().collect::<Vec<_>>()).unwrap_or_default()
+}
+
+// active sanctions act as the banned and muted roles
+pub fn get_sanction_roles(state: &State, authentication: &AuthenticationWithAddress) -> Vec<UserRole> {
+    let now = ic_cdk::api::time();
+    get_sanction_ids(state, authentication)
+        .iter()
+        .map(|sanction_id| state.sanctions.get(sanction_id).unwrap())
+        .filter(|sanction| is_active(sanction, now))
+        .map(|sanction| match sanction.kind {
+            SanctionKind::Ban => UserRole::Banned,
+            SanctionKind::Mute => UserRole::Muted
+        })
+        .collect()
+}
+
+fn get_sanction_response(state: &State, sanction_id: &u64) -> SanctionResponse {
+    let sanction = state.sanctions.get(sanction_id).unwrap();
+    let issuer = state.profiles.get(&sanction.issuer_id).unwrap();
+    SanctionResponse {
+        sanction_id: sanction_id.to_owned(),
+        authentication: sanction.authentication.to_owned(),
+        kind: sanction.kind.to_owned(),
+        reason: sanction.reason.to_owned(),
+        issuer: get_authentication_with_address(&issuer.authentication, &issuer.active_principal),
+        expires_at: sanction.expires_at,
+        timestamp: sanction.timestamp
+    }
+}
+
+// moderators sanction members, only admins sanction moderators
+fn add_sanction(authentication: AuthenticationWithAddress, kind: SanctionKind, reason: String, expires_at_opt: Option<u64>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::HideContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+    let caller_can_manage_roles = caller_has_permission(&caller, Permission::ManageRoles);
+
+    let authentication = normalize_authentication(authentication);
+    let reason = reason.trim().to_owned();
+    if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(format!("Reason should be between 1 and {} characters", MAX_REASON_LENGTH));
+    }
+    let timestamp = ic_cdk::api::time();
+    if expires_at_opt.map(|expires_at| expires_at <= timestamp).unwrap_or(false) {
+        return Err("Sanction should expire in the future".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let issuer_id = state.indexes.active_principal.get(&caller).cloned().unwrap();
+        if let Some(profile_id) = state.indexes.profile.get(&authentication) {
+            if profile_id == &issuer_id {
+                return Err("Cannot sanction yourself".to_owned());
+            }
+            if profile_has_permission(&state, profile_id, Permission::ManageRoles) {
+                return Err("Cannot sanction an admin".to_owned());
+            }
+            if !caller_can_manage_roles && profile_has_permission(&state, profile_id, Permission::HideContent) {
+                return Err("Only admins can sanction moderators".to_owned());
+            }
+        }
+
+        let sanction_id = uuid(&mut state);
+        let sanction = Sanction {
+            authentication: authentication.to_owned(),
+            kind,
+            reason,
+            issuer_id,
+            expires_at: expires_at_opt,
+            timestamp
+        };
+        state.sanctions.insert(sanction_id, sanction);
+        state.indexes.sanctions.entry(authentication).or_default().insert(sanction_id);
+
+        Ok(sanction_id)
+    })
+}
+
+// banned addresses cannot create content nor use any moderation permission
+#[update]
+#[candid_method(update)]
+fn ban_profile(authentication: AuthenticationWithAddress, reason: String, expires_at_opt: Option<u64>) -> Result<u64, String> {
+    add_sanction(authentication, SanctionKind::Ban, reason, expires_at_opt)
+}
+
+// muted addresses cannot create content
+#[update]
+#[candid_method(update)]
+fn mute_profile(authentication: AuthenticationWithAddress, reason: String, expires_at_opt: Option<u64>) -> Result<u64, String> {
+    add_sanction(authentication, SanctionKind::Mute, reason, expires_at_opt)
+}
+
+// sanctions issued by an admin are only lifted by an admin
+#[update]
+#[candid_method(update)]
+fn lift_sanction(sanction_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::HideContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+    let caller_can_manage_roles = caller_has_permission(&caller, Permission::ManageRoles);
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let sanction_opt = state.sanctions.get(&sanction_id);
+        if sanction_opt.is_none() {
+            return Err("Sanction does not exist".to_owned());
+        }
+        if !caller_can_manage_roles && profile_has_permission(&state, &sanction_opt.unwrap().issuer_id, Permission::ManageRoles) {
+            return Err("Only admins can lift sanctions issued by an admin".to_owned());
+        }
+        let sanction = state.sanctions.remove(&sanction_id).unwrap();
+
+        let sanction_ids = state.indexes.sanctions.get_mut(&sanction.authentication).unwrap();
+        sanction_ids.remove(&sanction_id);
+        if sanction_ids.is_empty() {
+            state.indexes.sanctions.remove(&sanction.authentication);
+        }
+
+        Ok(())
+    })
+}
+
+// sanctions of the address from the newest, including expired ones
+#[query]
+#[candid_method(query)]
+fn get_sanctions(authentication: AuthenticationWithAddress) -> Result<Vec<SanctionResponse>, String> {
+    let caller = ic_cdk::caller();
+    if !caller_has_permission(&caller, Permission::HideContent) {
+        return Err("Caller does not have permission".to_owned());
+    }
+
+    let authentication = normalize_authentication(authentication);
+    STATE.with(|s| {
+        let state = s.borrow();
+        let mut sanctions = get_sanction_ids(&state, &authentication)
+            .iter()
+            .map(|sanction_id| get_sanction_response(&state, sanction_id))
+            .collect::<Vec<_>>();
+        sanctions.sort_by_key(|sanction| std::cmp::Reverse(sanction.timestamp));
+        Ok(sanctions)
+    })
+}
+
+// active sanctions of the caller so the frontend can show the reason and the expiry
+#[query]
+#[candid_method(query)]
+fn get_caller_sanctions() -> Result<Vec<SanctionResponse>, String> {
+    let caller = ic_cdk::caller();
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let profile_id_opt = state.indexes.active_principal.get(&caller);
+        if profile_id_opt.is_none() {
+            return Err("Profile does not exists".to_owned());
+        }
+        let profile = state.profiles.get(profile_id_opt.unwrap()).unwrap();
+        let authentication = get_authentication_with_address(&profile.authentication, &profile.active_principal);
+
+        let now = ic_cdk::api::time();
+        let sanctions = get_sanction_ids(&state, &authentication)
+            .iter()
+            .filter(|sanction_id| is_active(state.sanctions.get(sanction_id).unwrap(), now))
+            .map(|sanction_id| get_sanction_response(&state, sanction_id))
+            .collect::<Vec<_>>();
+        Ok(sanctions)
+    })
+}
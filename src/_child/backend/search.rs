@@ -0,0 +1,231 @@
+use candid::{CandidType, Deserialize, candid_method};
+use ic_cdk::query;
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::state::{State, STATE, PostStatus, ReplyStatus, PostSummary, Permission};
+use crate::roles::caller_has_permission;
+use crate::get_post_summary;
+use crate::utils::migrate_in_batches;
+
+const MIN_TOKEN_LENGTH: usize = 2;
+const TITLE_WEIGHT: u64 = 2;
+const DEFAULT_SEARCH_LIMIT: u64 = 10;
+const MAX_SEARCH_LIMIT: u64 = 50;
+const MAX_SNIPPETS: usize = 3;
+const SNIPPET_LENGTH: usize = 120;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he", "her",
+    "his", "i", "if", "in", "into", "is", "it", "its", "me", "my", "no", "not", "of", "on", "or", "our",
+    "she", "so", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "us",
+    "was", "we", "were", "what", "when", "which", "who", "will", "with", "you", "your",
+];
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReplySnippet {
+    pub reply_id: u64,
+    pub snippet: String,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SearchResult {
+    pub post: PostSummary,
+    pub score: u64,
+    pub replies: Vec<ReplySnippet>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub next_cursor: Option<u64>,
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.chars().count() >= MIN_TOKEN_LENGTH && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn term_frequencies(tokens: Vec<String>, weight: u64, frequencies: &mut HashMap<String, u64>) {
+    for token in tokens {
+        *frequencies.entry(token).or_insert(0) += weight;
+    }
+}
+
+fn insert_entries(index: &mut HashMap<String, BTreeMap<u64, u64>>, id: u64, frequencies: HashMap<String, u64>) {
+    for (token, frequency) in frequencies {
+        index.entry(token).or_default().insert(id, frequency);
+    }
+}
+
+fn remove_entries(index: &mut HashMap<String, BTreeMap<u64, u64>>, id: u64, tokens: Vec<String>) {
+    for token in tokens {
+        if let Some(entries) = index.get_mut(&token) {
+            entries.remove(&id);
+            if entries.is_empty() {
+                index.remove(&token);
+            }
+        }
+    }
+}
+
+pub fn index_post(state: &mut State, post_id: u64) {
+    let post = state.posts.get(&post_id).unwrap();
+    let mut frequencies = HashMap::new();
+    term_frequencies(tokenize(&post.title), TITLE_WEIGHT, &mut frequencies);
+    term_frequencies(tokenize(&post.description), 1, &mut frequencies);
+    insert_entries(&mut state.indexes.search_posts, post_id, frequencies);
+}
+
+pub fn unindex_post(state: &mut State, post_id: u64) {
+    let post = state.posts.get(&post_id).unwrap();
+    let tokens = [tokenize(&post.title), tokenize(&post.description)].concat();
+    remove_entries(&mut state.indexes.search_posts, post_id, tokens);
+}
+
+pub fn index_reply(state: &mut State, reply_id: u64) {
+    let reply = state.replies.get(&reply_id).unwrap();
+    let mut frequencies = HashMap::new();
+    term_frequencies(tokenize(&reply.text), 1, &mut frequencies);
+    insert_entries(&mut state.indexes.search_replies, reply_id, frequencies);
+}
+
+pub fn unindex_reply(state: &mut State, reply_id: u64) {
+    let reply = state.replies.get(&reply_id).unwrap();
+    let tokens = tokenize(&reply.text);
+    remove_entries(&mut state.indexes.search_replies, reply_id, tokens);
+}
+
+fn index_post_with_replies(state: &mut State, post_id: u64) {
+    if !state.posts.contains_key(&post_id) {
+        return;
+    }
+    index_post(state, post_id);
+    let reply_ids = state.relations.reply_id_to_post_id.backward.get(&post_id).map(|x| x.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+    for reply_id in reply_ids {
+        index_reply(state, reply_id);
+    }
+}
+
+// the index is saved with the state, it is only built for the content of a version without search
+pub fn migrate_index(state: &mut State) {
+    if !state.indexes.search_posts.is_missing() && !state.indexes.search_replies.is_missing() {
+        return;
+    }
+    state.indexes.search_posts.clear();
+    state.indexes.search_replies.clear();
+    state.indexes.search_posts.start_rebuild();
+    state.indexes.search_replies.start_rebuild();
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    migrate_in_batches(post_ids, index_post_with_replies, |state| {
+        state.indexes.search_posts.finish_rebuild();
+        state.indexes.search_replies.finish_rebuild();
+    });
+}
+
+// text around the first matching token
+fn get_snippet(text: &str, tokens: &[String]) -> String {
+    // lowercasing can change the length of the text so each byte keeps the index of its original char
+    let mut text_lowercase = String::with_capacity(text.len());
+    let mut char_indexes = Vec::with_capacity(text.len());
+    for (char_index, c) in text.chars().enumerate() {
+        for lowercase in c.to_lowercase() {
+            text_lowercase.push(lowercase);
+            char_indexes.extend(std::iter::repeat_n(char_index, lowercase.len_utf8()));
+        }
+    }
+    let byte_index_opt = tokens.iter().filter_map(|token| text_lowercase.find(token.as_str())).min();
+    let char_index = byte_index_opt.map(|byte_index| char_indexes[byte_index]).unwrap_or(0);
+    let start = char_index.saturating_sub(SNIPPET_LENGTH / 4);
+    let snippet = text.chars().skip(start).take(SNIPPET_LENGTH).collect::<String>();
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if start + SNIPPET_LENGTH < text.chars().count() { "..." } else { "" };
+    format!("{}{}{}", prefix, snippet, suffix)
+}
+
+#[query]
+#[candid_method(query)]
+fn search(query: String, cursor_opt: Option<u64>, limit_opt: Option<u64>) -> SearchResponse {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let mut tokens = tokenize(&query);
+        tokens.sort();
+        tokens.dedup();
+
+        // hidden content stays indexed for moderators and is filtered by the caller permission
+        // score posts by their own content and their replies content
+        let mut scores: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut reply_scores: BTreeMap<u64, BTreeMap<u64, u64>> = BTreeMap::new();
+        for token in tokens.iter() {
+            if let Some(entries) = state.indexes.search_posts.get(token) {
+                for (post_id, frequency) in entries {
+                    *scores.entry(post_id.to_owned()).or_insert(0) += frequency;
+                }
+            }
+            if let Some(entries) = state.indexes.search_replies.get(token) {
+                for (reply_id, frequency) in entries {
+                    if !caller_can_hide_content && state.replies.get(reply_id).unwrap().status == ReplyStatus::Hidden {
+                        continue;
+                    }
+                    let (post_id, _) = state.relations.reply_id_to_post_id.forward.get(reply_id).unwrap().first_key_value().unwrap();
+                    *scores.entry(post_id.to_owned()).or_insert(0) += frequency;
+                    *reply_scores.entry(post_id.to_owned()).or_default().entry(reply_id.to_owned()).or_insert(0) += frequency;
+                }
+            }
+        }
+
+        let mut ranked = scores
+            .into_iter()
+            .filter(|(post_id, _)| caller_can_hide_content || state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(post_id_a, score_a), (post_id_b, score_b)| {
+            let timestamp_a = state.posts.get(post_id_a).unwrap().timestamp;
+            let timestamp_b = state.posts.get(post_id_b).unwrap().timestamp;
+            score_b.cmp(score_a).then(timestamp_b.cmp(&timestamp_a))
+        });
+
+        let cursor = cursor_opt.unwrap_or(0) as usize;
+        let limit = limit_opt.unwrap_or(DEFAULT_SEARCH_LIMIT).min(MAX_SEARCH_LIMIT) as usize;
+        let results = ranked
+            .iter()
+            .skip(cursor)
+            .take(limit)
+            .map(|(post_id, score)| {
+                let mut matching_replies = reply_scores.get(post_id).cloned().unwrap_or_default().into_iter().collect::<Vec<_>>();
+                matching_replies.sort_by(|(_, score_a), (_, score_b)| score_b.cmp(score_a));
+                let replies = matching_replies
+                    .iter()
+                    .take(MAX_SNIPPETS)
+                    .map(|(reply_id, _)| {
+                        let reply = state.replies.get(reply_id).unwrap();
+                        ReplySnippet { reply_id: reply_id.to_owned(), snippet: get_snippet(&reply.text, &tokens) }
+                    })
+                    .collect::<Vec<_>>();
+                SearchResult { post: get_post_summary(&state, post_id), score: score.to_owned(), replies }
+            })
+            .collect::<Vec<_>>();
+
+        let next_cursor = if cursor + limit < ranked.len() { Some((cursor + limit) as u64) } else { None };
+        SearchResponse { results, next_cursor }
+    })
+}
+
+#[test]
+fn snippets_around_matches() {
+    let tokens = vec!["needle".to_owned()];
+    let text = format!("{} Needle {}", "a".repeat(100), "b".repeat(200));
+    assert!(get_snippet(&text, &tokens).starts_with("...aaa"));
+    assert!(get_snippet(&text, &tokens).contains("Needle"));
+
+    // lowercase İ is longer than the original char
+    let text = format!("{} Needle {}", "İ".repeat(100), "b".repeat(200));
+    assert!(get_snippet(&text, &tokens).contains("Needle"));
+    assert!(get_snippet(&text, &tokens).starts_with("...İİİ"));
+}
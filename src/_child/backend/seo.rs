@@ -0,0 +1,227 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+use ic_certified_assets::types::DeleteAssetArguments;
+
+use crate::state::{State, STATE, PostStatus, ReplyStatus, Permission};
+use crate::domain::get_base_url;
+use crate::markdown::escape_html;
+use crate::syndication::format_rfc3339;
+use crate::utils::{get_asset, store_text, get_author_name};
+use crate::roles::caller_has_permission;
+use crate::syndication::update_post_feed;
+
+use std::cell::Cell;
+use std::time::Duration;
+
+// post pages are stored as certified assets and served before the frontend fallback
+pub const POST_PAGES_PATH: &str = "/post/";
+const INDEX_PATH: &str = "/index.html";
+const DEFAULT_TEMPLATE: &str = "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\" /></head><body><div id=\"root\"></div></body></html>";
+const MAX_META_DESCRIPTION_LENGTH: usize = 200;
+const MAX_PAGE_REPLIES: usize = 50;
+const SITEMAP_PATH: &str = "/sitemap.xml";
+const ROBOTS_PATH: &str = "/robots.txt";
+const MAX_SITEMAP_URLS: usize = 50_000;
+const MAX_ROBOTS_TXT_LENGTH: usize = 10_000;
+const SITEMAP_UPDATE_DELAY: Duration = Duration::from_secs(60);
+const RENDER_BATCH_SIZE: usize = 50;
+
+thread_local! {
+    static SITEMAP_UPDATE_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn get_post_page_key(post_id: u64) -> String {
+    format!("{}{}", POST_PAGES_PATH, post_id)
+}
+
+// the frontend index is used as template so the app still loads on top of the rendered content
+fn get_template() -> String {
+    if !ic_certified_assets::exists(INDEX_PATH.to_owned()) {
+        return DEFAULT_TEMPLATE.to_owned();
+    }
+    String::from_utf8(get_asset(INDEX_PATH.to_owned())).unwrap_or(DEFAULT_TEMPLATE.to_owned())
+}
+
+fn get_meta_description(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_META_DESCRIPTION_LENGTH {
+        return text;
+    }
+    format!("{}...", text.chars().take(MAX_META_DESCRIPTION_LENGTH).collect::<String>())
+}
+
+fn render_post_page(state: &State, post_id: u64, template: &str) -> String {
+    let post = state.posts.get(&post_id).unwrap();
+    let title = escape_html(&post.title);
+    let description = escape_html(&get_meta_description(&post.description));
+    let url = format!("{}{}", get_base_url(state), get_post_page_key(post_id));
+
+    let meta = [
+        format!("<title>{}</title>", title),
+        format!("<meta name=\"description\" content=\"{}\" />", description),
+        format!("<link rel=\"canonical\" href=\"{}\" />", url),
+        "<meta property=\"og:type\" content=\"article\" />".to_owned(),
+        format!("<meta property=\"og:title\" content=\"{}\" />", title),
+        format!("<meta property=\"og:description\" content=\"{}\" />", description),
+        format!("<meta property=\"og:url\" content=\"{}\" />", url),
+        "<meta name=\"twitter:card\" content=\"summary\" />".to_owned(),
+        format!("<meta name=\"twitter:title\" content=\"{}\" />", title),
+        format!("<meta name=\"twitter:description\" content=\"{}\" />", description),
+    ].concat();
+
+    let (author_id, _) = state.relations.profile_id_to_post_id.backward.get(&post_id).unwrap().first_key_value().unwrap();
+    // the oldest replies as in the thread
+    let mut reply_ids = state.relations.reply_id_to_post_id.backward
+        .get(&post_id)
+        .map(|reply_ids| reply_ids.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    reply_ids.retain(|reply_id| state.replies.get(reply_id).unwrap().status == ReplyStatus::Visible);
+    reply_ids.sort_by_key(|reply_id| state.replies.get(reply_id).unwrap().timestamp);
+    let replies = reply_ids
+        .iter()
+        .take(MAX_PAGE_REPLIES)
+        .map(|reply_id| {
+            let reply = state.replies.get(reply_id).unwrap();
+            let (reply_author_id, _) = state.relations.profile_id_to_reply_id.backward.get(reply_id).unwrap().first_key_value().unwrap();
+            format!("<article><h3>{}</h3>{}</article>", escape_html(&get_author_name(state, reply_author_id)), reply.text_html.to_owned().unwrap_or_default())
+        })
+        .collect::<String>();
+    let content = format!(
+        "<article><h1>{}</h1><p>{}</p>{}<section>{}</section></article>",
+        title,
+        escape_html(&get_author_name(state, author_id)),
+        post.description_html.to_owned().unwrap_or_default(),
+        replies
+    );
+
+    // replace the default title, add the meta tags and prerender the content in the app root
+    let mut html = template.to_owned();
+    if let (Some(start), Some(end)) = (html.find("<title>"), html.find("</title>")) {
+        html.replace_range(start..end + "</title>".len(), "");
+    }
+    html = html.replacen("</head>", &format!("{}</head>", meta), 1);
+    html.replacen("<div id=\"root\"></div>", &format!("<div id=\"root\">{}</div>", content), 1)
+}
+
+fn store_post_page(state: &State, post_id: u64, template: &str) {
+    let key = get_post_page_key(post_id);
+    let is_visible = state.posts.get(&post_id).map(|post| post.status == PostStatus::Visible).unwrap_or(false);
+    if !is_visible {
+        if ic_certified_assets::exists(key.to_owned()) {
+            ic_certified_assets::delete_asset(DeleteAssetArguments { key });
+        }
+        return;
+    }
+
+    store_text(&key, "text/html", render_post_page(state, post_id, template));
+}
+
+// renders the page again or removes it when the post is hidden or purged
+pub fn update_post_page(state: &State, post_id: u64) {
+    store_post_page(state, post_id, &get_template());
+}
+
+fn render_post_files_batch(mut post_ids: Vec<u64>) {
+    let remaining_post_ids = post_ids.split_off(post_ids.len().min(RENDER_BATCH_SIZE));
+    STATE.with(|s| {
+        let state = s.borrow();
+        let template = get_template();
+        for post_id in post_ids {
+            store_post_page(&state, post_id, &template);
+            update_post_feed(&state, post_id);
+        }
+    });
+
+    if !remaining_post_ids.is_empty() {
+        ic_cdk_timers::set_timer(Duration::ZERO, move || render_post_files_batch(remaining_post_ids));
+    }
+}
+
+// pages and replies feeds are removed with the frontend assets on upgrade so they are rendered again,
+// in batches on timers so large communities stay within the instruction limit
+pub fn render_post_files(state: &State) {
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    ic_cdk_timers::set_timer(Duration::ZERO, move || render_post_files_batch(post_ids));
+}
+
+fn get_default_robots_txt(state: &State) -> String {
+    format!("User-agent: *\nAllow: /\nSitemap: {}{}\n", get_base_url(state), SITEMAP_PATH)
+}
+
+fn store_robots_txt(state: &State) {
+    let robots_txt = state.robots_txt.to_owned().unwrap_or(get_default_robots_txt(state));
+    store_text(ROBOTS_PATH, "text/plain", robots_txt);
+}
+
+// lists the visible posts from the most recently active
+fn store_sitemap(state: &State) {
+    let base_url = get_base_url(state);
+    let urls = state.indexes.posts_by_last_activity.entries
+        .iter()
+        .rev()
+        .filter(|(_, post_id)| state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+        .take(MAX_SITEMAP_URLS)
+        .map(|(last_activity, post_id)| format!(
+            "<url><loc>{}{}</loc><lastmod>{}</lastmod></url>",
+            escape_html(&base_url),
+            get_post_page_key(post_id.to_owned()),
+            format_rfc3339(last_activity.to_owned())
+        ))
+        .collect::<String>();
+    let sitemap = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\"><url><loc>{}/</loc></url>{}</urlset>",
+        escape_html(&base_url),
+        urls
+    );
+    store_text(SITEMAP_PATH, "application/xml", sitemap);
+}
+
+// called when post visibility or activity changes, changes within the delay are batched
+// into a single rebuild as the sitemap lists every visible post
+pub fn update_sitemap() {
+    if SITEMAP_UPDATE_SCHEDULED.with(|scheduled| scheduled.replace(true)) {
+        return;
+    }
+    ic_cdk_timers::set_timer(SITEMAP_UPDATE_DELAY, || {
+        SITEMAP_UPDATE_SCHEDULED.with(|scheduled| scheduled.set(false));
+        STATE.with(|s| store_sitemap(&s.borrow()));
+    });
+}
+
+// absolute urls depend on the domain so these are rendered again on upgrade and when the custom domain is available
+pub fn render_crawler_files(state: &State) {
+    update_sitemap();
+    store_robots_txt(state);
+}
+
+// sets a custom robots.txt or restores the default one when none is given
+#[update]
+#[candid_method(update)]
+fn update_robots_txt(robots_txt_opt: Option<String>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_community = caller_has_permission(&caller, Permission::ManageCommunity);
+
+    if !caller_can_manage_community {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    if robots_txt_opt.as_ref().map(|robots_txt| robots_txt.len() > MAX_ROBOTS_TXT_LENGTH).unwrap_or(false) {
+        return Err("Robots.txt is too long".to_owned());
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.robots_txt = robots_txt_opt;
+        store_robots_txt(&state);
+        Ok(())
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_robots_txt() -> String {
+    STATE.with(|s| {
+        let state = s.borrow();
+        state.robots_txt.to_owned().unwrap_or(get_default_robots_txt(&state))
+    })
+}
@@ -1,7 +1,9 @@
-use candid::{CandidType, Deserialize, Principal};
+use candid::{CandidType, Deserialize, Principal, Nat};
+use candid::types::{Serializer, Type, TypeInner};
 
 use std::hash::Hash;
 use std::cmp::Ordering;
+use std::ops::{Deref, DerefMut};
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, BTreeSet};
 
@@ -78,7 +80,18 @@ pub struct Post {
     pub title: String,
     pub description: String,
     pub timestamp: u64,
-    pub status: PostStatus
+    pub status: PostStatus,
+    pub edited_at: Option<u64>,
+    pub tags: Option<Vec<String>>,
+    pub mentions: Option<Vec<Mention>>,
+    pub description_html: Option<String>,
+    pub flags: Option<PostFlags>
+}
+#[derive(Clone, CandidType, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct PostFlags {
+    pub pinned: bool,
+    pub locked: bool,
+    pub announcement: bool
 }
 #[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub enum ReplyStatus {
@@ -89,7 +102,10 @@ pub enum ReplyStatus {
 pub struct Reply {
     pub text: String,
     pub timestamp: u64,
-    pub status: ReplyStatus
+    pub status: ReplyStatus,
+    pub edited_at: Option<u64>,
+    pub mentions: Option<Vec<Mention>>,
+    pub text_html: Option<String>
 }
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub struct ReplyResponse {
@@ -98,7 +114,14 @@ pub struct ReplyResponse {
     pub authentication: AuthenticationWithAddress,
     pub reply_id: u64,
     pub likes: Vec<(u64, AuthenticationWithAddress)>,
-    pub status: ReplyStatus
+    pub status: ReplyStatus,
+    pub edited_at: Option<u64>,
+    pub parent_reply_id: Option<u64>,
+    pub depth: u64,
+    pub reactions: Vec<ReactionCount>,
+    pub mentions: Vec<MentionResponse>,
+    pub text_html: String,
+    pub attachments: Vec<AttachmentResponse>
 }
 
 #[derive(Clone, CandidType, Deserialize, Debug)]
@@ -111,6 +134,14 @@ pub struct PostResponse {
     pub timestamp: u64,
     pub status: PostStatus,
     pub replies: Vec<ReplyResponse>,
+    pub edited_at: Option<u64>,
+    pub tags: Vec<String>,
+    pub poll: Option<PollResponse>,
+    pub reactions: Vec<ReactionCount>,
+    pub mentions: Vec<MentionResponse>,
+    pub description_html: String,
+    pub attachments: Vec<AttachmentResponse>,
+    pub flags: PostFlags
 }
 #[derive(Clone, CandidType, Deserialize, Debug)]
 pub struct ProfileWithStatsResponse {
@@ -123,7 +154,9 @@ pub struct ProfileWithStatsResponse {
     pub join_date: u64,
     pub total_posts: u64,
     pub total_replies: u64,
-    pub total_likes: u64
+    pub total_likes: u64,
+    pub total_followers: u64,
+    pub total_following: u64
 }
 
 #[derive(Clone, CandidType, Deserialize, Debug)]
@@ -143,20 +176,264 @@ pub struct PostSummary {
     pub timestamp: u64,
     pub authentication: AuthenticationWithAddress,
     pub replies_count: u64,
+    pub likes_count: u64,
     pub last_activity: u64,
-    pub status: PostStatus
+    pub status: PostStatus,
+    pub category_id: Option<u64>,
+    pub tags: Vec<String>,
+    pub flags: PostFlags
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostSort {
+    Newest,
+    LastActivity,
+    MostLiked,
+    MostReplied
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PostCursor {
+    pub value: u64,
+    pub post_id: u64
+}
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PostsPage {
+    pub posts: Vec<PostSummary>,
+    pub next_cursor: Option<PostCursor>,
+    pub pinned_posts: Vec<PostSummary> // first page only, not counted in the limit
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Poll {
+    pub options: Vec<String>,
+    pub closes_at: u64,
+    pub multi_choice: bool,
+    pub closed: bool,
+    pub votes: BTreeMap<u64, Vec<u64>> // profile id to option indexes
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PollOptionResponse {
+    pub text: String,
+    pub votes: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PollResponse {
+    pub options: Vec<PollOptionResponse>,
+    pub closes_at: u64,
+    pub multi_choice: bool,
+    pub closed: bool,
+    pub voters: u64,
+    pub caller_votes: Vec<u64>
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Conversation {
+    pub timestamp: u64,
+    pub last_message_at: u64,
+    pub unread: BTreeMap<u64, u64> // profile id to unread messages
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Message {
+    pub text: String,
+    pub timestamp: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MessageResponse {
+    pub message_id: u64,
+    pub text: String,
+    pub timestamp: u64,
+    pub sender: AuthenticationWithAddress
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ConversationResponse {
+    pub conversation_id: u64,
+    pub participant: AuthenticationWithAddress,
+    pub last_message: Option<MessageResponse>,
+    pub last_message_at: u64,
+    pub unread_count: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MessagesPage {
+    pub messages: Vec<MessageResponse>,
+    pub next_cursor: Option<u64>
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Mention {
+    pub text: String,
+    pub profile_id: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MentionResponse {
+    pub text: String,
+    pub authentication: AuthenticationWithAddress
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Draft {
+    pub title: String,
+    pub description: String,
+    pub category_id: Option<u64>,
+    pub tags: Vec<String>,
+    pub publish_at: Option<u64>,
+    pub timestamp: u64,
+    pub updated_at: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DraftResponse {
+    pub draft_id: u64,
+    pub title: String,
+    pub description: String,
+    pub category_id: Option<u64>,
+    pub tags: Vec<String>,
+    pub publish_at: Option<u64>,
+    pub timestamp: u64,
+    pub updated_at: u64
+}
+
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub enum SanctionKind {
+    Ban,
+    Mute
+}
+// keyed on the address so new principals of the same wallet are still sanctioned
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Sanction {
+    pub authentication: AuthenticationWithAddress,
+    pub kind: SanctionKind,
+    pub reason: String,
+    pub issuer_id: u64,
+    pub expires_at: Option<u64>,
+    pub timestamp: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SanctionResponse {
+    pub sanction_id: u64,
+    pub authentication: AuthenticationWithAddress,
+    pub kind: SanctionKind,
+    pub reason: String,
+    pub issuer: AuthenticationWithAddress,
+    pub expires_at: Option<u64>,
+    pub timestamp: u64
 }
 
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Attachment {
+    pub content_type: String,
+    pub size: u64,
+    pub timestamp: u64,
+    pub upload: Option<AttachmentUpload> // none once the upload is committed
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AttachmentUpload {
+    pub batch_id: Nat,
+    pub chunk_ids: Vec<Nat>,
+    pub uploaded: u64,
+    pub expires_at: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AttachmentResponse {
+    pub attachment_id: u64,
+    pub url: String,
+    pub content_type: String,
+    pub size: u64,
+    pub timestamp: u64
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    Reply,
+    PostLike,
+    ReplyLike,
+    Mention
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Notification {
+    pub notification_id: u64,
+    pub kind: NotificationKind,
+    pub actor_id: u64,
+    pub post_id: u64,
+    pub reply_id: Option<u64>,
+    pub timestamp: u64,
+    pub read: bool
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationResponse {
+    pub notification_id: u64,
+    pub kind: NotificationKind,
+    pub actor: AuthenticationWithAddress,
+    pub post_id: u64,
+    pub reply_id: Option<u64>,
+    pub timestamp: u64,
+    pub read: bool
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationsPage {
+    pub notifications: Vec<NotificationResponse>,
+    pub next_cursor: Option<u64>
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Category {
+    pub name: String,
+    pub description: String,
+    pub order: u64,
+    pub admin_only: bool,
+    pub timestamp: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CategoryResponse {
+    pub category_id: u64,
+    pub name: String,
+    pub description: String,
+    pub order: u64,
+    pub admin_only: bool,
+    pub timestamp: u64
+}
 
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum UserRole {
-    Admin
+    Admin,
+    Moderator,
+    Member,
+    Muted,
+    Banned
+}
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Permission {
+    CreateContent,
+    HideContent,
+    PinPosts,
+    ManageCategories,
+    ManageRoles,
+    ManageCommunity
 }
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct  Role {
     pub timestamp: u64,
     pub role: UserRole
 }
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReactionKind {
+    Like,
+    Love,
+    Laugh,
+    Wow,
+    Sad,
+    Angry
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Reaction {
+    pub kind: ReactionKind,
+    pub timestamp: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReactionCount {
+    pub kind: ReactionKind,
+    pub count: u64,
+    pub caller_reacted: bool
+}
+// NOTE likes are migrated to reactions on upgrade
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct LikedPost {
     pub timestamp: u64
@@ -166,6 +443,25 @@ pub struct LikedReply {
     pub timestamp: u64
 }
 
+#[derive(CandidType, Deserialize, Clone, Default, Debug)]
+pub struct PostStats {
+    pub replies_count: u64,
+    pub likes_count: u64,
+    pub last_activity: u64
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PostRevision {
+    pub title: String,
+    pub description: String,
+    pub timestamp: u64
+}
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReplyRevision {
+    pub text: String,
+    pub timestamp: u64
+}
+
 #[derive(Default, CandidType, Clone, Deserialize, Debug)]
 pub struct Relation<X: Ord, Y: Ord> {
     pub forward: BTreeMap<X, BTreeMap<Y, ()>>,
@@ -233,26 +529,141 @@ impl <K: Eq + Ord, V: Eq + PartialOrd> ValueEntry<K, V> {
         (&self.0.0, &self.0.1)
     }
 }
+// ids ordered by a value (value, id), with the current value of each id to update it
+#[derive(Default, CandidType, Clone, Deserialize, Debug)]
+pub struct OrderedIndex {
+    pub values: HashMap<u64, u64>,
+    pub entries: BTreeSet<(u64, u64)>,
+}
+impl OrderedIndex {
+    pub fn insert(&mut self, id: u64, value: u64) {
+        if let Some(prev_value) = self.values.insert(id, value) {
+            self.entries.remove(&(prev_value, id));
+        }
+        self.entries.insert((value, id));
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        if let Some(prev_value) = self.values.remove(&id) {
+            self.entries.remove(&(prev_value, id));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.entries.clear();
+    }
+}
+
+// state fields added after the first release are encoded as opt
+// so the state saved by an older version still decodes, with the field empty
+#[derive(Default, Clone, Debug)]
+pub struct Upgradable<T> {
+    value: T,
+    missing: bool,
+    rebuilding: bool
+}
+
+impl<T> Upgradable<T> {
+    // the field was not in the restored state so it is migrated from the older data
+    pub fn is_missing(&self) -> bool {
+        self.missing
+    }
+    // a field rebuilt across several calls is saved as missing until it is complete,
+    // so an upgrade in between starts the rebuild again
+    pub fn start_rebuild(&mut self) {
+        self.rebuilding = true;
+    }
+    pub fn finish_rebuild(&mut self) {
+        self.missing = false;
+        self.rebuilding = false;
+    }
+}
+
+impl<T: CandidType> CandidType for Upgradable<T> {
+    fn _ty() -> Type {
+        TypeInner::Opt(T::ty()).into()
+    }
+    fn idl_serialize<S: Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        if self.rebuilding {
+            return serializer.serialize_option::<T>(None);
+        }
+        serializer.serialize_option(Some(&self.value))
+    }
+}
+impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for Upgradable<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(|value| Upgradable {
+            missing: value.is_none(),
+            value: value.unwrap_or_default(),
+            rebuilding: false
+        })
+    }
+}
+impl<T> Deref for Upgradable<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T> DerefMut for Upgradable<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
 #[derive(Default, CandidType, Clone, Deserialize, Debug)]
 pub struct Relations {
     pub profile_id_to_post_id: Relation<u64, u64>,
     pub profile_id_to_reply_id: Relation<u64, u64>,
     pub reply_id_to_post_id: Relation<u64, u64>,
+    pub reply_id_to_parent_reply_id: Upgradable<Relation<u64, u64>>,
     pub profile_id_to_role_id: Relation<u64, u64>,
+    pub post_id_to_reaction_id: Upgradable<Relation<u64, u64>>,
+    pub reply_id_to_reaction_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_reaction_id: Upgradable<Relation<u64, u64>>,
     pub post_id_to_liked_post_id: Relation<u64, u64>,
     pub profile_id_to_liked_post_id: Relation<u64, u64>,
     pub reply_id_to_liked_reply_id: Relation<u64, u64>,
     pub profile_id_to_liked_reply_id: Relation<u64, u64>,
+    pub post_id_to_post_revision_id: Upgradable<Relation<u64, u64>>,
+    pub reply_id_to_reply_revision_id: Upgradable<Relation<u64, u64>>,
+    pub category_id_to_post_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_conversation_id: Upgradable<Relation<u64, u64>>,
+    pub conversation_id_to_message_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_message_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_blocked_profile_id: Upgradable<Relation<u64, u64>>,
+    pub post_id_to_mentioned_profile_id: Upgradable<Relation<u64, u64>>,
+    pub reply_id_to_mentioned_profile_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_attachment_id: Upgradable<Relation<u64, u64>>,
+    pub post_id_to_attachment_id: Upgradable<Relation<u64, u64>>,
+    pub reply_id_to_attachment_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_bookmarked_post_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_followed_profile_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_subscribed_post_id: Upgradable<Relation<u64, u64>>,
+    pub profile_id_to_draft_id: Upgradable<Relation<u64, u64>>,
 }
 
 #[derive(Default, CandidType, Clone, Deserialize, Debug)]
 pub struct Indexes {
     pub profile: HashMap<AuthenticationWithAddress, u64>,
     pub active_principal: HashMap<Principal, u64>,
+    pub handle: Upgradable<HashMap<String, u64>>,
+    pub has_reacted: Upgradable<HashMap<(u64, u64, ReactionKind), u64>>,
     pub has_liked_post: HashMap<(u64, u64), ()>,
     pub has_liked_reply: HashMap<(u64, u64), ()>,
     pub most_liked_replies: HashMap<u64, BTreeSet<ValueEntry<u64, u64>>>,
     pub most_liked_posts: HashMap<u64, BTreeSet<ValueEntry<u64, u64>>>,
+    pub search_posts: Upgradable<HashMap<String, BTreeMap<u64, u64>>>,
+    pub search_replies: Upgradable<HashMap<String, BTreeMap<u64, u64>>>,
+    pub tags: Upgradable<HashMap<String, BTreeSet<u64>>>,
+    pub conversation: Upgradable<HashMap<(u64, u64), u64>>,
+    pub posts_by_timestamp: Upgradable<OrderedIndex>,
+    pub posts_by_last_activity: Upgradable<OrderedIndex>,
+    pub posts_by_likes: Upgradable<OrderedIndex>,
+    pub posts_by_replies: Upgradable<OrderedIndex>,
+    pub pinned_posts: Upgradable<BTreeSet<u64>>,
+    pub sanctions: Upgradable<HashMap<AuthenticationWithAddress, BTreeSet<u64>>>,
 }
 #[derive(CandidType, Clone, Deserialize, Debug)]
 pub struct Metadata {
@@ -266,8 +677,20 @@ pub struct State {
     pub posts: BTreeMap<u64, Post>,
     pub replies: BTreeMap<u64, Reply>,
     pub roles: BTreeMap<u64, Role>,
+    pub reactions: Upgradable<BTreeMap<u64, Reaction>>,
     pub liked_posts: BTreeMap<u64, LikedPost>,
     pub liked_replies: BTreeMap<u64, LikedReply>,
+    pub post_revisions: Upgradable<BTreeMap<u64, PostRevision>>,
+    pub reply_revisions: Upgradable<BTreeMap<u64, ReplyRevision>>,
+    pub post_stats: Upgradable<BTreeMap<u64, PostStats>>,
+    pub categories: Upgradable<BTreeMap<u64, Category>>,
+    pub polls: Upgradable<BTreeMap<u64, Poll>>,
+    pub conversations: Upgradable<BTreeMap<u64, Conversation>>,
+    pub messages: Upgradable<BTreeMap<u64, Message>>,
+    pub inboxes: Upgradable<BTreeMap<u64, Vec<Notification>>>, // profile id to notifications from oldest to newest
+    pub attachments: Upgradable<BTreeMap<u64, Attachment>>,
+    pub drafts: Upgradable<BTreeMap<u64, Draft>>,
+    pub sanctions: Upgradable<BTreeMap<u64, Sanction>>,
     pub relations: Relations,
     pub indexes: Indexes,
     pub parent: Option<Principal>,
@@ -275,7 +698,10 @@ pub struct State {
     pub track: Option<String>,
     pub txn_log: BTreeMap<u128, Transaction>,
     pub uuid_count: u64,
-    pub domain: Option<Domain>
+    pub domain: Option<Domain>,
+    pub max_reply_depth: Option<u64>,
+    pub robots_txt: Option<String>,
+    pub community_name: Option<String>
 }
 
 thread_local! {
@@ -0,0 +1,263 @@
+use candid::candid_method;
+use ic_cdk::{update, query};
+use ic_certified_assets::types::DeleteAssetArguments;
+
+use crate::state::{State, STATE, PostStatus, ReplyStatus, Permission};
+use crate::roles::caller_has_permission;
+use crate::domain::get_base_url;
+use crate::markdown::escape_html;
+use crate::seo::POST_PAGES_PATH;
+use crate::utils::{store_text, get_author_name};
+
+pub const RSS_PATH: &str = "/feed.xml";
+pub const ATOM_PATH: &str = "/atom.xml";
+pub const JSON_FEED_PATH: &str = "/feed.json";
+const DEFAULT_FEED_TITLE: &str = "Community";
+const MAX_COMMUNITY_NAME_LENGTH: usize = 100;
+const MAX_FEED_ITEMS: usize = 20;
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+struct FeedItem {
+    id: String,
+    title: String,
+    url: String,
+    author: String,
+    content_html: String,
+    timestamp: u64
+}
+
+// converts days since the unix epoch to (year, month, day) of the gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn split_timestamp(timestamp: u64) -> (i64, (i64, u64, u64), u64) {
+    let seconds = timestamp / 1_000_000_000;
+    let days = (seconds / 86400) as i64;
+    (days, civil_from_days(days), seconds % 86400)
+}
+
+// timestamp in nanoseconds to RFC 3339 used by atom, json feeds and the sitemap
+pub fn format_rfc3339(timestamp: u64) -> String {
+    let (_, (year, month, day), time) = split_timestamp(timestamp);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+// timestamp in nanoseconds to RFC 822 used by rss feeds
+fn format_rfc822(timestamp: u64) -> String {
+    let (days, (year, month, day), time) = split_timestamp(timestamp);
+    let weekday = DAYS[days.rem_euclid(7) as usize];
+    format!("{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT", weekday, day, MONTHS[month as usize - 1], year, time / 3600, time % 3600 / 60, time % 60)
+}
+
+fn get_post_url(state: &State, post_id: u64) -> String {
+    format!("{}{}{}", get_base_url(state), POST_PAGES_PATH, post_id)
+}
+
+fn get_post_feed_key(post_id: u64) -> String {
+    format!("{}{}{}", POST_PAGES_PATH, post_id, RSS_PATH)
+}
+
+// latest visible posts from the newest
+fn get_post_items(state: &State) -> Vec<FeedItem> {
+    state.indexes.posts_by_timestamp.entries
+        .iter()
+        .rev()
+        .filter(|(_, post_id)| state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+        .take(MAX_FEED_ITEMS)
+        .map(|(_, post_id)| {
+            let post = state.posts.get(post_id).unwrap();
+            let (author_id, _) = state.relations.profile_id_to_post_id.backward.get(post_id).unwrap().first_key_value().unwrap();
+            let url = get_post_url(state, post_id.to_owned());
+            FeedItem {
+                id: url.to_owned(),
+                title: post.title.to_owned(),
+                url,
+                author: get_author_name(state, author_id),
+                content_html: post.description_html.to_owned().unwrap_or_default(),
+                timestamp: post.timestamp
+            }
+        })
+        .collect()
+}
+
+// latest visible replies of the post from the newest
+fn get_reply_items(state: &State, post_id: u64) -> Vec<FeedItem> {
+    let post = state.posts.get(&post_id).unwrap();
+    let mut reply_ids = state.relations.reply_id_to_post_id.backward
+        .get(&post_id)
+        .map(|reply_ids| reply_ids.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    reply_ids.retain(|reply_id| state.replies.get(reply_id).unwrap().status == ReplyStatus::Visible);
+    reply_ids.sort_by_key(|reply_id| std::cmp::Reverse(state.replies.get(reply_id).unwrap().timestamp));
+
+    reply_ids
+        .iter()
+        .take(MAX_FEED_ITEMS)
+        .map(|reply_id| {
+            let reply = state.replies.get(reply_id).unwrap();
+            let (author_id, _) = state.relations.profile_id_to_reply_id.backward.get(reply_id).unwrap().first_key_value().unwrap();
+            let url = get_post_url(state, post_id);
+            FeedItem {
+                id: format!("{}#{}", url, reply_id),
+                title: format!("Re: {}", post.title),
+                url,
+                author: get_author_name(state, author_id),
+                content_html: reply.text_html.to_owned().unwrap_or_default(),
+                timestamp: reply.timestamp
+            }
+        })
+        .collect()
+}
+
+fn render_rss(title: &str, link: &str, self_url: &str, items: &[FeedItem]) -> String {
+    let items = items
+        .iter()
+        .map(|item| format!(
+            "<item><title>{}</title><link>{}</link><guid isPermaLink=\"false\">{}</guid><dc:creator>{}</dc:creator><pubDate>{}</pubDate><description>{}</description></item>",
+            escape_html(&item.title),
+            escape_html(&item.url),
+            escape_html(&item.id),
+            escape_html(&item.author),
+            format_rfc822(item.timestamp),
+            escape_html(&item.content_html)
+        ))
+        .collect::<String>();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><channel><title>{}</title><link>{}</link><description>{}</description><atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\" />{}</channel></rss>",
+        escape_html(title),
+        escape_html(link),
+        escape_html(title),
+        escape_html(self_url),
+        items
+    )
+}
+
+fn render_atom(title: &str, link: &str, self_url: &str, items: &[FeedItem]) -> String {
+    let updated = items.iter().map(|item| item.timestamp).max().unwrap_or(0);
+    let entries = items
+        .iter()
+        .map(|item| format!(
+            "<entry><id>{}</id><title>{}</title><link href=\"{}\" /><author><name>{}</name></author><updated>{}</updated><content type=\"html\">{}</content></entry>",
+            escape_html(&item.id),
+            escape_html(&item.title),
+            escape_html(&item.url),
+            escape_html(&item.author),
+            format_rfc3339(item.timestamp),
+            escape_html(&item.content_html)
+        ))
+        .collect::<String>();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><feed xmlns=\"http://www.w3.org/2005/Atom\"><id>{}</id><title>{}</title><link href=\"{}\" /><link href=\"{}\" rel=\"self\" /><updated>{}</updated>{}</feed>",
+        escape_html(self_url),
+        escape_html(title),
+        escape_html(link),
+        escape_html(self_url),
+        format_rfc3339(updated),
+        entries
+    )
+}
+
+fn render_json_feed(title: &str, link: &str, self_url: &str, items: &[FeedItem]) -> String {
+    let items = items
+        .iter()
+        .map(|item| serde_json::json!({
+            "id": item.id,
+            "url": item.url,
+            "title": item.title,
+            "content_html": item.content_html,
+            "date_published": format_rfc3339(item.timestamp),
+            "authors": [{ "name": item.author }]
+        }))
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": title,
+        "home_page_url": link,
+        "feed_url": self_url,
+        "items": items
+    }).to_string()
+}
+
+// renders the community feeds again, called when posts are created, edited, hidden or purged
+pub fn update_feeds(state: &State) {
+    let base_url = get_base_url(state);
+    let items = get_post_items(state);
+    let title = state.community_name.as_deref().unwrap_or(DEFAULT_FEED_TITLE);
+
+    let rss = render_rss(title, &base_url, &format!("{}{}", base_url, RSS_PATH), &items);
+    store_text(RSS_PATH, "application/rss+xml", rss);
+    let atom = render_atom(title, &base_url, &format!("{}{}", base_url, ATOM_PATH), &items);
+    store_text(ATOM_PATH, "application/atom+xml", atom);
+    let json_feed = render_json_feed(title, &base_url, &format!("{}{}", base_url, JSON_FEED_PATH), &items);
+    store_text(JSON_FEED_PATH, "application/feed+json", json_feed);
+}
+
+// the community name is the title of its feeds, the default title is restored when none is given
+#[update]
+#[candid_method(update)]
+fn update_community_name(community_name_opt: Option<String>) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let caller_can_manage_community = caller_has_permission(&caller, Permission::ManageCommunity);
+
+    if !caller_can_manage_community {
+        return Err("Caller does not have permission".to_owned())
+    }
+
+    let community_name_opt = community_name_opt.map(|community_name| community_name.trim().to_owned()).filter(|community_name| !community_name.is_empty());
+    if community_name_opt.as_ref().map(|community_name| community_name.chars().count() > MAX_COMMUNITY_NAME_LENGTH).unwrap_or(false) {
+        return Err(format!("Community name should be at most {} characters", MAX_COMMUNITY_NAME_LENGTH));
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.community_name = community_name_opt;
+        update_feeds(&state);
+        Ok(())
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_community_name() -> Option<String> {
+    STATE.with(|s| s.borrow().community_name.to_owned())
+}
+
+// renders the replies feed of the post again or removes it when the post is hidden or purged
+pub fn update_post_feed(state: &State, post_id: u64) {
+    let key = get_post_feed_key(post_id);
+    let post_opt = state.posts.get(&post_id);
+    if post_opt.is_none() || post_opt.unwrap().status == PostStatus::Hidden {
+        if ic_certified_assets::exists(key.to_owned()) {
+            ic_certified_assets::delete_asset(DeleteAssetArguments { key });
+        }
+        return;
+    }
+
+    let title = post_opt.unwrap().title.to_owned();
+    let items = get_reply_items(state, post_id);
+    let rss = render_rss(&title, &get_post_url(state, post_id), &format!("{}{}", get_base_url(state), key), &items);
+    store_text(&key, "application/rss+xml", rss);
+}
+
+#[test]
+fn feed_date_formats() {
+    let timestamp = 1_609_459_200 * 1_000_000_000; // 2021-01-01 00:00:00 UTC
+    assert_eq!(format_rfc3339(timestamp), "2021-01-01T00:00:00Z");
+    assert_eq!(format_rfc822(timestamp), "Fri, 01 Jan 2021 00:00:00 GMT");
+
+    let timestamp = 1_709_210_096 * 1_000_000_000; // 2024-02-29 12:34:56 UTC
+    assert_eq!(format_rfc3339(timestamp), "2024-02-29T12:34:56Z");
+    assert_eq!(format_rfc822(timestamp), "Thu, 29 Feb 2024 12:34:56 GMT");
+}
@@ -0,0 +1,139 @@
+use candid::candid_method;
+use ic_cdk::query;
+
+use crate::state::{State, STATE, PostStatus, PostSort, PostCursor, PostsPage, Permission};
+use crate::roles::caller_has_permission;
+use crate::get_page;
+use crate::utils::migrate_in_batches;
+
+const MAX_TAG_LENGTH: usize = 32;
+const MAX_TAGS_PER_POST: usize = 5;
+const DEFAULT_TAGS_LIMIT: u64 = 20;
+const MAX_TAGS_LIMIT: u64 = 100;
+
+// lowercase, without a leading `#` and with whitespace replaced by `-`
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag
+        .trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_owned());
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!("Tag should be at most {} characters", MAX_TAG_LENGTH));
+    }
+    if !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err("Tag can only contain letters, numbers, dashes and underscores".to_owned());
+    }
+    Ok(tag)
+}
+
+pub fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized_tags = Vec::new();
+    for tag in tags {
+        let normalized_tag = normalize_tag(&tag)?;
+        if !normalized_tags.contains(&normalized_tag) {
+            normalized_tags.push(normalized_tag);
+        }
+    }
+    if normalized_tags.len() > MAX_TAGS_PER_POST {
+        return Err(format!("Post can have at most {} tags", MAX_TAGS_PER_POST));
+    }
+    Ok(normalized_tags)
+}
+
+pub fn get_post_tags(state: &State, post_id: &u64) -> Vec<String> {
+    state.posts.get(post_id).unwrap().tags.to_owned().unwrap_or_default()
+}
+
+pub fn index_post_tags(state: &mut State, post_id: u64) {
+    for tag in get_post_tags(state, &post_id) {
+        state.indexes.tags.entry(tag).or_default().insert(post_id);
+    }
+}
+
+pub fn unindex_post_tags(state: &mut State, post_id: u64) {
+    for tag in get_post_tags(state, &post_id) {
+        if let Some(post_ids) = state.indexes.tags.get_mut(&tag) {
+            post_ids.remove(&post_id);
+            if post_ids.is_empty() {
+                state.indexes.tags.remove(&tag);
+            }
+        }
+    }
+}
+
+// the index is saved with the state, it is only built for the posts of a version without tags
+pub fn migrate_tag_index(state: &mut State) {
+    if !state.indexes.tags.is_missing() {
+        return;
+    }
+    state.indexes.tags.clear();
+    state.indexes.tags.start_rebuild();
+    let post_ids = state.posts.keys().cloned().collect::<Vec<_>>();
+    migrate_in_batches(
+        post_ids,
+        |state, post_id| if state.posts.contains_key(&post_id) { index_post_tags(state, post_id) },
+        |state| state.indexes.tags.finish_rebuild()
+    );
+}
+
+// most used tags with the number of posts, hidden posts are only counted for moderators
+#[query]
+#[candid_method(query)]
+fn get_popular_tags(limit_opt: Option<u64>) -> Vec<(String, u64)> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let mut tags = state
+            .indexes
+            .tags
+            .iter()
+            .map(|(tag, post_ids)| {
+                let count = post_ids
+                    .iter()
+                    .filter(|post_id| caller_can_hide_content || state.posts.get(post_id).unwrap().status == PostStatus::Visible)
+                    .count() as u64;
+                (tag.to_owned(), count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        tags.sort_by(|(tag_a, count_a), (tag_b, count_b)| count_b.cmp(count_a).then(tag_a.cmp(tag_b)));
+
+        let limit = limit_opt.unwrap_or(DEFAULT_TAGS_LIMIT).min(MAX_TAGS_LIMIT) as usize;
+        tags.truncate(limit);
+        tags
+    })
+}
+
+// posts that have all the given tags
+#[query]
+#[candid_method(query)]
+fn get_tagged_posts_page(tags: Vec<String>, sort: PostSort, cursor_opt: Option<PostCursor>, limit_opt: Option<u64>) -> Result<PostsPage, String> {
+    let caller = ic_cdk::caller();
+    let caller_can_hide_content = caller_has_permission(&caller, Permission::HideContent);
+
+    let tags = normalize_tags(tags)?;
+    if tags.is_empty() {
+        return Err("Tags cannot be empty".to_owned());
+    }
+
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        let post_ids = tags
+            .iter()
+            .map(|tag| state.indexes.tags.get(tag).cloned().unwrap_or_default())
+            .reduce(|a, b| a.intersection(&b).cloned().collect())
+            .unwrap_or_default();
+
+        Ok(get_page(&state, sort, cursor_opt, limit_opt, caller_can_hide_content, |post_id| post_ids.contains(post_id)))
+    })
+}
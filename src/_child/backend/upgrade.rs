@@ -2,6 +2,7 @@ use std::ops::Sub;
 use candid::{CandidType, Deserialize, Nat, Principal};
 use crate::utils::{get_asset, get_content_type, format_number};
 use crate::state::STATE;
+use crate::attachments::UPLOADS_PATH;
 use ic_cdk::api::management_canister::main::*;
 use ic_cdk::api::canister_balance;
 use serde_bytes::ByteBuf;
@@ -170,7 +171,7 @@ pub fn replace_assets_from_temp() {
   // cleanup previous assets
   let prev_assets = &assets.iter().filter(|k| !k.key.starts_with("/temp")).collect::<Vec<_>>();
   for asset  in prev_assets {
-    if asset.key == "/.well-known/ic-domains".to_owned() { continue; }
+    if asset.key == "/.well-known/ic-domains".to_owned() || asset.key.starts_with(UPLOADS_PATH) { continue; }
     ic_certified_assets::delete_asset(DeleteAssetArguments { key: asset.key.to_owned() });
   }
 
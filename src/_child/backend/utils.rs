@@ -1,13 +1,16 @@
 use candid::{Nat, Principal};
-use ic_certified_assets::types::{GetArg, GetChunkArg};
+use ic_certified_assets::types::{GetArg, GetChunkArg, StoreArg};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount, DEFAULT_SUBACCOUNT};
 use num_traits::ToPrimitive;
-use std::cell::RefMut;
+use serde_bytes::ByteBuf;
 use std::collections::hash_map;
 use std::hash::{Hash, Hasher};
 use std::ops::Div;
+use std::time::Duration;
 
-use crate::state::{STATE, UserRole, State};
+use crate::state::{STATE, State};
+
+const MIGRATION_BATCH_SIZE: usize = 100;
 
 pub fn get_asset(key: String) -> Vec<u8> {
     // get asset length
@@ -36,8 +39,47 @@ pub fn get_asset(key: String) -> Vec<u8> {
     return content;
 }
 
+// stores generated text such as pages, feeds and crawler files as a certified asset
+pub fn store_text(key: &str, content_type: &str, content: String) {
+    let store_args = StoreArg {
+        key: key.to_owned(),
+        content_type: content_type.to_owned(),
+        content_encoding: "identity".to_owned(),
+        content: ByteBuf::from(content.as_bytes().to_vec()),
+        sha256: None
+    };
+    ic_certified_assets::store(store_args);
+}
+
+pub fn get_author_name(state: &State, profile_id: &u64) -> String {
+    state.profiles.get(profile_id).unwrap().name.to_owned()
+}
+
+
+// migrates the content of an older version in batches on timers so large communities stay within
+// the instruction limit, ids removed in the meantime are skipped by the migration
+pub fn migrate_in_batches(ids: Vec<u64>, migrate: fn(&mut State, u64), finish: fn(&mut State)) {
+    ic_cdk_timers::set_timer(Duration::ZERO, move || migrate_batch(ids, migrate, finish));
+}
+
+fn migrate_batch(mut ids: Vec<u64>, migrate: fn(&mut State, u64), finish: fn(&mut State)) {
+    let remaining_ids = ids.split_off(ids.len().min(MIGRATION_BATCH_SIZE));
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        for id in ids {
+            migrate(&mut state, id);
+        }
+        if remaining_ids.is_empty() {
+            finish(&mut state);
+        }
+    });
+
+    if !remaining_ids.is_empty() {
+        ic_cdk_timers::set_timer(Duration::ZERO, move || migrate_batch(remaining_ids, migrate, finish));
+    }
+}
 
-pub fn uuid(state: &mut RefMut<'_, State>) -> u64 {
+pub fn uuid(state: &mut State) -> u64 {
     state.uuid_count += 1;
     let mut s = hash_map::DefaultHasher::new();
     state.uuid_count.hash(&mut s);
@@ -67,29 +109,6 @@ pub fn format_number(num: u64) -> String {
     format!("{:.*}{}", digits, num1, si[index].1)
 }
 
-pub fn get_user_roles(caller: &Principal) -> Option<Vec<UserRole>> {
-    STATE.with(|s| {
-        let state = s.borrow();
-        let profile_id_opt = state.indexes.active_principal.get(caller);
-
-        if profile_id_opt.is_none() {
-            return None;
-        }
-        let role_ids =  state.relations.profile_id_to_role_id.forward.get(profile_id_opt.unwrap());
-
-        if role_ids.is_none() {
-            return Some(vec![]);
-        }
-        let user_roles = role_ids
-            .unwrap()
-            .iter()
-            .map(|(role_id, _)| state.roles.get(role_id).unwrap().role.to_owned())
-            .collect::<Vec<_>>();
-        Some(user_roles)
-    })
-}
-
-
 pub fn default_account(owner: &Principal) -> Account {
     Account {
         owner: owner.clone(),
@@ -2,7 +2,7 @@
 use ed25519_dalek::{VerifyingKey, Signature, Verifier};
 use crate::state::*;
 
-fn checksum_evm_address (address: String) -> String {
+pub fn checksum_evm_address(address: String) -> String {
     let hash =  easy_hasher::easy_hasher::keccak256(&address.trim_start_matches("0x").to_lowercase());
     let hash_hex = hash.to_hex_string();
 
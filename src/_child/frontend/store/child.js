@@ -55,7 +55,7 @@ const idlFactory = ({ IDL }) => {
 		status: PostStatus,
 		post_id: IDL.Nat64
 	});
-	const UserRole =IDL.Variant({ Admin: IDL.Null }) 
+	const UserRole =IDL.Variant({ Admin: IDL.Null, Moderator: IDL.Null, Member: IDL.Null, Muted: IDL.Null, Banned: IDL.Null }) 
 
 	const Profile = IDL.Record({
 		name: IDL.Text,
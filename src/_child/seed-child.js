@@ -34,17 +34,17 @@ const count = argv.count ?? 5
 	// create and like one post for each profile
 	const promisesPosts = []
 	for (const actor of actors) {
-		const promise = mainActor.create_post('hello', '').then(res => actor.like_post(res.Ok.post_id))
+		const promise = mainActor.create_post('hello', '', [], []).then(res => actor.like_post(res.Ok.post_id))
 		promisesPosts.push(promise)
 	}
 	await Promise.all(promisesPosts)
 
 	// create and like one reply for each profile
-	const createdPost = await mainActor.create_post('hello', '')
+	const createdPost = await mainActor.create_post('hello', '', [], [])
 	const postId = createdPost.Ok.post_id
 	const promisesReplies = []
 	for (const actor of actors) {
-		const promise = mainActor.create_reply(postId, 'hello').then(res =>  actor.like_reply(res.Ok.reply_id))
+		const promise = mainActor.create_reply(postId, 'hello', []).then(res =>  actor.like_reply(res.Ok.reply_id))
 		promisesReplies.push(promise)
 	}
 
@@ -501,6 +501,7 @@ impl State {
                         headers: vec![("Location".to_string(), replacement_url)],
                         body: RcBytes::from(ByteBuf::default()),
                         streaming_strategy: None,
+                        upgrade: None,
                     };
                 }
             }
@@ -518,6 +519,7 @@ impl State {
                                 name, err
                             ))),
                             streaming_strategy: None,
+                            upgrade: None,
                         };
                     }
                 }
@@ -540,6 +542,7 @@ impl State {
                     path, err
                 ))),
                 streaming_strategy: None,
+                upgrade: None,
             },
         }
     }
@@ -832,6 +835,7 @@ fn build_ok(
         headers,
         body,
         streaming_strategy,
+        upgrade: None,
     }
 }
 
@@ -841,6 +845,7 @@ fn build_404(certificate_header: HeaderField) -> HttpResponse {
         headers: vec![certificate_header],
         body: RcBytes::from(ByteBuf::from("not found")),
         streaming_strategy: None,
+        upgrade: None,
     }
 }
 
@@ -122,6 +122,8 @@ pub struct HttpResponse {
     pub headers: Vec<HeaderField>,
     pub body: RcBytes,
     pub streaming_strategy: Option<StreamingStrategy>,
+    // asks the gateway to repeat the request as an update call
+    pub upgrade: Option<bool>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -9,6 +9,25 @@ const childFactory = ({ IDL }) => {
 		Evm: IDL.Record({ address: IDL.Text }),
 		Svm: IDL.Record({ address: IDL.Text }),
 	});
+	const UserRole = IDL.Variant({ Admin: IDL.Null, Moderator: IDL.Null, Member: IDL.Null, Muted: IDL.Null, Banned: IDL.Null });
+	const Permission = IDL.Variant({
+		CreateContent: IDL.Null,
+		HideContent: IDL.Null,
+		PinPosts: IDL.Null,
+		ManageCategories: IDL.Null,
+		ManageRoles: IDL.Null,
+		ManageCommunity: IDL.Null,
+	});
+	const SanctionKind = IDL.Variant({ Ban: IDL.Null, Mute: IDL.Null });
+	const SanctionResponse = IDL.Record({
+		sanction_id: IDL.Nat64,
+		authentication: AuthenticationWithAddress,
+		kind: SanctionKind,
+		reason: IDL.Text,
+		issuer: AuthenticationWithAddress,
+		expires_at: IDL.Opt(IDL.Nat64),
+		timestamp: IDL.Nat64,
+	});
 
 	const ReplyStatus = IDL.Variant({
 		Visible: IDL.Null,
@@ -20,13 +39,52 @@ const childFactory = ({ IDL }) => {
 		Hidden: IDL.Null
 	})
 
+	const ReactionKind = IDL.Variant({
+		Like: IDL.Null,
+		Love: IDL.Null,
+		Laugh: IDL.Null,
+		Wow: IDL.Null,
+		Sad: IDL.Null,
+		Angry: IDL.Null
+	})
+
+	const ReactionCount = IDL.Record({ kind: ReactionKind, count: IDL.Nat64, caller_reacted: IDL.Bool })
+
+	const PostFlags = IDL.Record({ pinned: IDL.Bool, locked: IDL.Bool, announcement: IDL.Bool })
+
+	const MentionResponse = IDL.Record({ text: IDL.Text, authentication: AuthenticationWithAddress })
+
+	const AttachmentResponse = IDL.Record({
+		attachment_id: IDL.Nat64,
+		url: IDL.Text,
+		content_type: IDL.Text,
+		size: IDL.Nat64,
+		timestamp: IDL.Nat64
+	});
+
 	const ReplyResponse = IDL.Record({
 		text: IDL.Text,
 		timestamp: IDL.Nat64,
 		authentication: AuthenticationWithAddress,
 		likes: IDL.Vec(IDL.Tuple(IDL.Nat64, AuthenticationWithAddress)),
 		reply_id: IDL.Nat64,
-		status: ReplyStatus
+		status: ReplyStatus,
+		edited_at: IDL.Opt(IDL.Nat64),
+		parent_reply_id: IDL.Opt(IDL.Nat64),
+		depth: IDL.Nat64,
+		reactions: IDL.Vec(ReactionCount),
+		mentions: IDL.Vec(MentionResponse),
+		text_html: IDL.Text,
+		attachments: IDL.Vec(AttachmentResponse)
+	});
+
+	const PollResponse = IDL.Record({
+		options: IDL.Vec(IDL.Record({ text: IDL.Text, votes: IDL.Nat64 })),
+		closes_at: IDL.Nat64,
+		multi_choice: IDL.Bool,
+		closed: IDL.Bool,
+		voters: IDL.Nat64,
+		caller_votes: IDL.Vec(IDL.Nat64),
 	});
 
 	const PostResponse = IDL.Record({
@@ -36,7 +94,26 @@ const childFactory = ({ IDL }) => {
 		replies: IDL.Vec(ReplyResponse),
 		likes: IDL.Vec(IDL.Tuple(IDL.Nat64, AuthenticationWithAddress)),
 		status: PostStatus,
-		post_id: IDL.Nat64
+		post_id: IDL.Nat64,
+		edited_at: IDL.Opt(IDL.Nat64),
+		tags: IDL.Vec(IDL.Text),
+		poll: IDL.Opt(PollResponse),
+		reactions: IDL.Vec(ReactionCount),
+		mentions: IDL.Vec(MentionResponse),
+		description_html: IDL.Text,
+		attachments: IDL.Vec(AttachmentResponse),
+		flags: PostFlags
+	});
+
+	const PostRevision = IDL.Record({
+		title: IDL.Text,
+		description: IDL.Text,
+		timestamp: IDL.Nat64
+	});
+
+	const ReplyRevision = IDL.Record({
+		text: IDL.Text,
+		timestamp: IDL.Nat64
 	});
 
 	const Profile = IDL.Record({
@@ -48,6 +125,21 @@ const childFactory = ({ IDL }) => {
 		last_login: IDL.Nat64,
 	});
 
+	const ProfileWithStatsResponse = IDL.Record({
+		name: IDL.Text,
+		description: IDL.Text,
+		authentication: Authentication,
+		active_principal: IDL.Principal,
+		roles: IDL.Vec(UserRole),
+		last_login: IDL.Nat64,
+		join_date: IDL.Nat64,
+		total_posts: IDL.Nat64,
+		total_replies: IDL.Nat64,
+		total_likes: IDL.Nat64,
+		total_followers: IDL.Nat64,
+		total_following: IDL.Nat64
+	});
+
 	const PostSummary = IDL.Record({
 		title: IDL.Text,
 		post_id: IDL.Nat64,
@@ -55,7 +147,92 @@ const childFactory = ({ IDL }) => {
 		authentication: AuthenticationWithAddress,
 		timestamp: IDL.Nat64,
 		replies_count: IDL.Nat64,
+		likes_count: IDL.Nat64,
 		last_activity: IDL.Nat64,
+		category_id: IDL.Opt(IDL.Nat64),
+		tags: IDL.Vec(IDL.Text),
+		flags: PostFlags,
+	});
+
+	const DraftResponse = IDL.Record({
+		draft_id: IDL.Nat64,
+		title: IDL.Text,
+		description: IDL.Text,
+		category_id: IDL.Opt(IDL.Nat64),
+		tags: IDL.Vec(IDL.Text),
+		publish_at: IDL.Opt(IDL.Nat64),
+		timestamp: IDL.Nat64,
+		updated_at: IDL.Nat64
+	});
+
+	const Category = IDL.Record({
+		category_id: IDL.Nat64,
+		name: IDL.Text,
+		description: IDL.Text,
+		order: IDL.Nat64,
+		admin_only: IDL.Bool,
+		timestamp: IDL.Nat64,
+	});
+
+	const PostSort = IDL.Variant({
+		Newest: IDL.Null,
+		LastActivity: IDL.Null,
+		MostLiked: IDL.Null,
+		MostReplied: IDL.Null
+	});
+
+	const PostCursor = IDL.Record({ value: IDL.Nat64, post_id: IDL.Nat64 });
+
+	const PostsPage = IDL.Record({
+		posts: IDL.Vec(PostSummary),
+		next_cursor: IDL.Opt(PostCursor),
+		pinned_posts: IDL.Vec(PostSummary),
+	});
+
+	const SearchResult = IDL.Record({
+		post: PostSummary,
+		score: IDL.Nat64,
+		replies: IDL.Vec(IDL.Record({ reply_id: IDL.Nat64, snippet: IDL.Text })),
+	});
+
+	const SearchResponse = IDL.Record({
+		results: IDL.Vec(SearchResult),
+		next_cursor: IDL.Opt(IDL.Nat64),
+	});
+
+	const MessageResponse = IDL.Record({
+		message_id: IDL.Nat64,
+		text: IDL.Text,
+		timestamp: IDL.Nat64,
+		sender: AuthenticationWithAddress,
+	});
+
+	const ConversationResponse = IDL.Record({
+		conversation_id: IDL.Nat64,
+		participant: AuthenticationWithAddress,
+		last_message: IDL.Opt(MessageResponse),
+		last_message_at: IDL.Nat64,
+		unread_count: IDL.Nat64,
+	});
+
+	const MessagesPage = IDL.Record({
+		messages: IDL.Vec(MessageResponse),
+		next_cursor: IDL.Opt(IDL.Nat64),
+	});
+
+	const NotificationResponse = IDL.Record({
+		notification_id: IDL.Nat64,
+		kind: IDL.Variant({ Reply: IDL.Null, PostLike: IDL.Null, ReplyLike: IDL.Null, Mention: IDL.Null }),
+		actor: AuthenticationWithAddress,
+		post_id: IDL.Nat64,
+		reply_id: IDL.Opt(IDL.Nat64),
+		timestamp: IDL.Nat64,
+		read: IDL.Bool,
+	});
+
+	const NotificationsPage = IDL.Record({
+		notifications: IDL.Vec(NotificationResponse),
+		next_cursor: IDL.Opt(IDL.Nat64),
 	});
 
 	const authenticationWith = IDL.Variant({
@@ -76,24 +253,118 @@ const childFactory = ({ IDL }) => {
 	})
 	const Metadata = IDL.Record({version: IDL.Text, track: IDL.Text})
 
+	const HeaderField = IDL.Tuple(IDL.Text, IDL.Text)
+	const HttpRequest = IDL.Record({
+		url: IDL.Text,
+		method: IDL.Text,
+		body: IDL.Vec(IDL.Nat8),
+		headers: IDL.Vec(HeaderField),
+	})
+	const StreamingCallbackToken = IDL.Record({
+		key: IDL.Text,
+		sha256: IDL.Opt(IDL.Vec(IDL.Nat8)),
+		index: IDL.Nat,
+		content_encoding: IDL.Text,
+	})
+	const StreamingStrategy = IDL.Variant({
+		Callback: IDL.Record({ token: StreamingCallbackToken, callback: IDL.Func([], [], []) }),
+	})
+	const HttpResponse = IDL.Record({
+		body: IDL.Vec(IDL.Nat8),
+		headers: IDL.Vec(HeaderField),
+		streaming_strategy: IDL.Opt(StreamingStrategy),
+		status_code: IDL.Nat16,
+		upgrade: IDL.Opt(IDL.Bool),
+	})
+
 	return IDL.Service({
 		create_profile: IDL.Func([authenticationWith], [IDL.Variant({ Ok: Profile, Err: IDL.Text })], ["update"]),
-		create_post: IDL.Func([IDL.Text, IDL.Text], [IDL.Variant({ Ok: PostSummary, Err: IDL.Text })], ["update"]),
-		create_reply: IDL.Func([IDL.Nat64, IDL.Text], [IDL.Variant({ Ok: ReplyResponse, Err: IDL.Text })], ["update"]),
+		update_profile: IDL.Func([IDL.Opt(IDL.Text), IDL.Opt(IDL.Text)], [IDL.Variant({ Ok: Profile, Err: IDL.Text })], ["update"]),
+		create_post: IDL.Func([IDL.Text, IDL.Text, IDL.Opt(IDL.Nat64), IDL.Opt(IDL.Vec(IDL.Text))], [IDL.Variant({ Ok: PostSummary, Err: IDL.Text })], ["update"]),
+		create_reply: IDL.Func([IDL.Nat64, IDL.Text, IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: ReplyResponse, Err: IDL.Text })], ["update"]),
+		create_poll: IDL.Func([IDL.Text, IDL.Text, IDL.Vec(IDL.Text), IDL.Nat64, IDL.Bool], [IDL.Variant({ Ok: PostSummary, Err: IDL.Text })], ["update"]),
+		vote: IDL.Func([IDL.Nat64, IDL.Vec(IDL.Nat64)], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
 		update_post_status: IDL.Func([IDL.Nat64, PostStatus], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		update_post_flags: IDL.Func([IDL.Nat64, PostFlags], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		purge_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		create_attachment: IDL.Func([IDL.Text, IDL.Nat64], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
+		upload_attachment_chunk: IDL.Func([IDL.Nat64, IDL.Vec(IDL.Nat8)], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		commit_attachment: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: AttachmentResponse, Err: IDL.Text })], ["update"]),
+		attach_to_post: IDL.Func([IDL.Nat64, IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		attach_to_reply: IDL.Func([IDL.Nat64, IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		delete_attachment: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_attachments: IDL.Func([], [IDL.Variant({ Ok: IDL.Tuple(IDL.Vec(AttachmentResponse), IDL.Nat64), Err: IDL.Text })], ["query"]),
+		bookmark_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		unbookmark_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_bookmarks: IDL.Func([PostSort, IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: PostsPage, Err: IDL.Text })], ["query"]),
+		follow_profile: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		unfollow_profile: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		subscribe_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		unsubscribe_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_feed: IDL.Func([IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: PostsPage, Err: IDL.Text })], ["query"]),
+		save_draft: IDL.Func([IDL.Opt(IDL.Nat64), IDL.Text, IDL.Text, IDL.Opt(IDL.Nat64), IDL.Opt(IDL.Vec(IDL.Text))], [IDL.Variant({ Ok: DraftResponse, Err: IDL.Text })], ["update"]),
+		delete_draft: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		publish_draft: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: PostSummary, Err: IDL.Text })], ["update"]),
+		schedule_draft: IDL.Func([IDL.Nat64, IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		cancel_scheduled_draft: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_drafts: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(DraftResponse), Err: IDL.Text })], ["query"]),
+		get_scheduled_drafts: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(DraftResponse), Err: IDL.Text })], ["query"]),
+		update_robots_txt: IDL.Func([IDL.Opt(IDL.Text)], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_robots_txt: IDL.Func([], [IDL.Text], ["query"]),
+		update_community_name: IDL.Func([IDL.Opt(IDL.Text)], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_community_name: IDL.Func([], [IDL.Opt(IDL.Text)], ["query"]),
+		http_request: IDL.Func([HttpRequest], [HttpResponse], ["query"]),
+		http_request_update: IDL.Func([HttpRequest], [HttpResponse], ["update"]),
+		assign_role: IDL.Func([AuthenticationWithAddress, UserRole], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		revoke_role: IDL.Func([AuthenticationWithAddress, UserRole], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_permissions: IDL.Func([], [IDL.Vec(Permission)], ["query"]),
+		ban_profile: IDL.Func([AuthenticationWithAddress, IDL.Text, IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
+		mute_profile: IDL.Func([AuthenticationWithAddress, IDL.Text, IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
+		lift_sanction: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_sanctions: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Vec(SanctionResponse), Err: IDL.Text })], ["query"]),
+		get_caller_sanctions: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(SanctionResponse), Err: IDL.Text })], ["query"]),
 		update_reply_status: IDL.Func([IDL.Nat64, ReplyStatus], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		create_category: IDL.Func([IDL.Text, IDL.Text, IDL.Nat64, IDL.Bool], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
+		update_category: IDL.Func([IDL.Nat64, IDL.Text, IDL.Text, IDL.Nat64, IDL.Bool], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		delete_category: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		move_post: IDL.Func([IDL.Nat64, IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		edit_post: IDL.Func([IDL.Nat64, IDL.Text, IDL.Text, IDL.Opt(IDL.Vec(IDL.Text))], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		edit_reply: IDL.Func([IDL.Nat64, IDL.Text], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
 		like_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
 		unlike_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
 		like_reply: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
 		unlike_reply: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		react_to_post: IDL.Func([IDL.Nat64, ReactionKind], [IDL.Variant({ Ok: IDL.Bool, Err: IDL.Text })], ["update"]),
+		react_to_reply: IDL.Func([IDL.Nat64, ReactionKind], [IDL.Variant({ Ok: IDL.Bool, Err: IDL.Text })], ["update"]),
+		send_message: IDL.Func([AuthenticationWithAddress, IDL.Text], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["update"]),
+		mark_conversation_read: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		block_profile: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		unblock_profile: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_conversations: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(ConversationResponse), Err: IDL.Text })], ["query"]),
+		get_messages: IDL.Func([IDL.Nat64, IDL.Opt(IDL.Nat64), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: MessagesPage, Err: IDL.Text })], ["query"]),
+		mark_notifications_read: IDL.Func([IDL.Opt(IDL.Vec(IDL.Nat64))], [IDL.Variant({ Ok: IDL.Null, Err: IDL.Text })], ["update"]),
+		get_notifications: IDL.Func([IDL.Opt(IDL.Nat64), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: NotificationsPage, Err: IDL.Text })], ["query"]),
+		get_unread_notifications_count: IDL.Func([], [IDL.Variant({ Ok: IDL.Nat64, Err: IDL.Text })], ["query"]),
+		get_mentioned_posts_page: IDL.Func([PostSort, IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: PostsPage, Err: IDL.Text })], ["query"]),
+		get_blocked_profiles: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(AuthenticationWithAddress), Err: IDL.Text })], ["query"]),
 		get_most_liked_posts: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Vec(PostResponse), Err: IDL.Text })], ["query"]),
 		get_most_liked_replies: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Vec(IDL.Tuple(IDL.Nat64, ReplyResponse)), Err: IDL.Text })], ["query"]),
 		get_profile: IDL.Func([], [IDL.Variant({ Ok: Profile, Err: IDL.Text })], ["query"]),
+		get_profile_by_auth: IDL.Func([AuthenticationWithAddress], [IDL.Opt(ProfileWithStatsResponse)], ["query"]),
 		get_post: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: PostResponse, Err: IDL.Text })], ["query"]),
 		get_posts: IDL.Func([], [IDL.Vec(PostSummary)], ["query"]),
+		get_posts_page: IDL.Func([PostSort, IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [PostsPage], ["query"]),
+		get_categories: IDL.Func([], [IDL.Vec(Category)], ["query"]),
+		get_category_posts_page: IDL.Func([IDL.Nat64, PostSort, IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: PostsPage, Err: IDL.Text })], ["query"]),
+		get_popular_tags: IDL.Func([IDL.Opt(IDL.Nat64)], [IDL.Vec(IDL.Tuple(IDL.Text, IDL.Nat64))], ["query"]),
+		get_tagged_posts_page: IDL.Func([IDL.Vec(IDL.Text), PostSort, IDL.Opt(PostCursor), IDL.Opt(IDL.Nat64)], [IDL.Variant({ Ok: PostsPage, Err: IDL.Text })], ["query"]),
+		search: IDL.Func([IDL.Text, IDL.Opt(IDL.Nat64), IDL.Opt(IDL.Nat64)], [SearchResponse], ["query"]),
 		get_most_recent_posts: IDL.Func([AuthenticationWithAddress], [IDL.Variant({ Ok: IDL.Vec(PostSummary), Err: IDL.Text })], ["query"]),
 		get_hidden_posts: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(PostResponse), Err: IDL.Text })], ["query"]),
 		get_hidden_replies: IDL.Func([], [IDL.Variant({ Ok: IDL.Vec(IDL.Tuple(IDL.Nat64, ReplyResponse)), Err: IDL.Text })], ["query"]),
+		get_max_reply_depth: IDL.Func([], [IDL.Nat64], ["query"]),
+		get_post_revisions: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Vec(PostRevision), Err: IDL.Text })], ["query"]),
+		get_reply_revisions: IDL.Func([IDL.Nat64], [IDL.Variant({ Ok: IDL.Vec(ReplyRevision), Err: IDL.Text })], ["query"]),
 		get_metadata: IDL.Func([],[IDL.Variant({ 'Ok': Metadata, 'Err': IDL.Text })], ["query"]),
 		upgrade_canister: IDL.Func([IDL.Text, IDL.Text], [], ["update"]),
 		get_next_upgrades: IDL.Func([], [IDL.Variant({ 'Ok': IDL.Vec(UpgradeWithTrack), 'Err': IDL.Text })], ["update"])
@@ -5,12 +5,16 @@ const { ethers } = require('ethers')
 const web3 = require('@solana/web3.js')
 const bs58 = require('bs58')
 
-const { setupTests, checkDfxRunning, getAgent, getCanisters } = require('../src/_meta/shared/utils')
+const { setupTests, checkDfxRunning, getAgent, getCanisters, sleep } = require('../src/_meta/shared/utils')
 const {  getSignatureAndMessage, getSignatureAndMessageSvm } = require('../src/_meta/shared/identity')
 const { childFactory } = require('../src/_meta/shared/idl')
 
 setupTests()
 
+const parseResponse = (response) => ({ status: response.status_code, upgrade: response.upgrade, headers: Object.fromEntries(response.headers), body: new TextDecoder().decode(Uint8Array.from(response.body)) })
+const getAsset = async (actor, url, method = 'GET') => parseResponse(await actor.http_request({ url, method, body: [], headers: [] }))
+const getAssetUpdate = async (actor, url, method = 'GET') => parseResponse(await actor.http_request_update({ url, method, body: [], headers: [] }))
+
 describe('Testing with done', () => {
 
 	let actorBackendEvm, actorBackendSvm, actorBackendIc, signerEvm, identityEvm, signerSvm, identitySvm, identityIc, canisters
@@ -56,7 +60,7 @@ describe('Testing with done', () => {
 		expect(address).toBe(signerAddress)
 		expect(identityEvm.getPrincipal().toString()).toBe(principal)
 		
-		await actorBackendEvm.create_post('hello', '')
+		await actorBackendEvm.create_post('hello', '', [], [])
 		const userPosts = await actorBackendEvm.get_most_recent_posts({Evm: { address: signerAddress}})
 		expect(userPosts.Ok.length).toBe(1)
 		identityEvm = Ed25519KeyIdentity.generate()
@@ -75,7 +79,7 @@ describe('Testing with done', () => {
 		expect(address2).toBe(signerAddress)
 		expect(identityEvm.getPrincipal().toString()).toBe(principal2)
 
-		await actorBackendEvm.create_post('hello', '')
+		await actorBackendEvm.create_post('hello', '', [], [])
 		const userPosts2 = await actorBackendEvm.get_most_recent_posts({Evm: { address: signerAddress}})
 		expect(userPosts2.Ok.length).toBe(2)
 		
@@ -90,7 +94,7 @@ describe('Testing with done', () => {
 		const address = profile.Ok.authentication.Svm.address;
 		expect(address).toBe(signerSvm.publicKey.toString());
 
-		await actorBackendSvm.create_post('hello', '')
+		await actorBackendSvm.create_post('hello', '', [], [])
 		const userPosts = await actorBackendSvm.get_most_recent_posts({Svm: { address: signerSvm.publicKey.toString()}})
 		expect(userPosts.Ok.length).toBe(1)
 
@@ -108,7 +112,7 @@ describe('Testing with done', () => {
 		const address2 = profile2.Ok.authentication.Svm.address;
 		expect(address2).toBe(signerSvm.publicKey.toString());
 
-		await actorBackendSvm.create_post('hello', '')
+		await actorBackendSvm.create_post('hello', '', [], [])
 		const userPosts2 = await actorBackendSvm.get_most_recent_posts({Svm: { address: signerSvm.publicKey.toString()}})
 		expect(userPosts2.Ok.length).toBe(2)
   	})
@@ -119,7 +123,7 @@ describe('Testing with done', () => {
 		const principal = profile.Ok.active_principal
 		expect(principal.toString()).toBe(identityIc.getPrincipal().toString())
 
-		await actorBackendIc.create_post('hello', '')
+		await actorBackendIc.create_post('hello', '', [], [])
 		const userPosts = await actorBackendIc.get_most_recent_posts({Ic: {principal: principal}})
 		expect(userPosts.Ok.length).toBe(1)
 
@@ -128,7 +132,7 @@ describe('Testing with done', () => {
 		const principal1 = profile1.Ok.active_principal
     	expect(principal1.toString()).toBe(identityIc.getPrincipal().toString())
 
-		await actorBackendIc.create_post('hello', '')
+		await actorBackendIc.create_post('hello', '', [], [])
 		const userPosts2 = await actorBackendIc.get_most_recent_posts({Ic: {principal: principal1}})
 		expect(userPosts2.Ok.length).toBe(2)
   	});
@@ -136,11 +140,11 @@ describe('Testing with done', () => {
 	test('Should create and get a post', async () => {
 		
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 
 		// create a reply
-		await actorBackendIc.create_reply(postId, 'hello')
+		await actorBackendIc.create_reply(postId, 'hello', [])
 
 		// get user last post
 		const principal = identityIc.getPrincipal()
@@ -174,7 +178,7 @@ describe('Testing with done', () => {
 		// create and like one post for each profile
 		const promisesPosts = []
 		for (const actor of actors) {
-			const promise = actorBackendIc.create_post('hello', '')
+			const promise = actorBackendIc.create_post('hello', '', [], [])
 			.then((res) => Promise.all([actor.like_post(res.Ok.post_id), Promise.resolve(res.Ok.post_id)]))
 			.then((res) => res[1])
 			promisesPosts.push(promise)
@@ -192,7 +196,7 @@ describe('Testing with done', () => {
 		expect(postIds.every((postId, index)=> mostLikedPosts2.Ok[index].post_id === postId)).toBe(true)
 		
 		// like not "most liked post"
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 		await actors[2].like_post(postId)
 		const mostLikedPosts3 = await actorBackendIc.get_most_liked_posts({Ic: { principal: principal}})
@@ -236,11 +240,11 @@ describe('Testing with done', () => {
 		expect(mostLikedReplies.Ok.length).toBe(0)
 
 		// create and like one reply for each profile
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 		const promisesReplies = []
 		for (const actor of actors) {
-			const promise = actorBackendIc.create_reply(postId, 'hello')
+			const promise = actorBackendIc.create_reply(postId, 'hello', [])
 				.then(res => Promise.all([actor.like_reply(res.Ok.reply_id), Promise.resolve(res.Ok.reply_id)]))
 				.then(res=> res[1])
 			promisesReplies.push(promise)
@@ -259,7 +263,7 @@ describe('Testing with done', () => {
 		expect(replyIds.every((replyId, index)=> mostLikedReplies2.Ok[index][1].reply_id === replyId)).toBe(true)
 		
 		// like not "most liked reply"
-		const createdReply = await actorBackendIc.create_reply(postId, 'hello')
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
 		const replyId = createdReply.Ok.reply_id
 		await actors[2].like_reply(replyId)
 		const mostLikedReplies3 = await actorBackendIc.get_most_liked_replies({Ic: { principal: principal}})
@@ -285,7 +289,7 @@ describe('Testing with done', () => {
 		const principal = identityIc.getPrincipal()
 
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 
 		const posts = await actorBackendIc.get_posts()
@@ -320,10 +324,10 @@ describe('Testing with done', () => {
 		const principal = identityIc.getPrincipal()
 
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 		// create a reply 
-		const createdReply = await actorBackendIc.create_reply(postId, 'hello')
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
 		const replyId = createdReply.Ok.reply_id
 
 		const posts = await actorBackendIc.get_posts()
@@ -355,7 +359,7 @@ describe('Testing with done', () => {
 	})
 	test.skip('Should get hidden posts', async () => {
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 
 		const hiddenPosts = await actorBackendIc.get_hidden_posts()
@@ -369,11 +373,11 @@ describe('Testing with done', () => {
 	})
 	test.skip('Should get hidden replies', async () => {
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 
 		// create a reply
-		const createdReply = await actorBackendIc.create_reply(postId, 'hello')
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
 		const replyId = createdReply.Ok.reply_id
 
 		const hiddenReplies = await actorBackendIc.get_hidden_replies()
@@ -387,7 +391,7 @@ describe('Testing with done', () => {
 	})
 	test('Should create, like and unlike post', async () => {
 		// create a post
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
 
 		// like a post
@@ -407,9 +411,9 @@ describe('Testing with done', () => {
 	})
 	test('Should create, like and unlike reply', async () => {
 		// create a post and a reply
-		const createdPost = await actorBackendIc.create_post('hello', '')
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
 		const postId = createdPost.Ok.post_id
-		const createRely = await actorBackendIc.create_reply(postId, 'hello')
+		const createRely = await actorBackendIc.create_reply(postId, 'hello', [])
 		const replyId = createRely.Ok.reply_id
 
 		// like a reply
@@ -427,4 +431,793 @@ describe('Testing with done', () => {
 		const post1 = await actorBackendIc.get_post(postId)
 		expect(post1.Ok.replies[0].likes.length).toBe(0)
 	})
+	test('Should update profile name and description', async () => {
+		// update profile
+		const name = `user_${Date.now() % 100000}`
+		const profile = await actorBackendIc.update_profile([name], ['hello'])
+		expect(profile.Ok.name).toBe(name)
+		expect(profile.Ok.description).toBe('hello')
+
+		// name is taken
+		const identity = Ed25519KeyIdentity.generate()
+		const actor = Actor.createActor(childFactory, { agent: getAgent('http://127.0.0.1:8000', identity), canisterId: canisters.child.local })
+		const otherProfile = await actor.create_profile({Ic: null})
+		expect(otherProfile.Ok.name).not.toBe('')
+		const profile1 = await actor.update_profile([name.toUpperCase()], [])
+		expect(profile1.Err).toBe('Name is already taken')
+
+		// invalid name
+		const profile2 = await actor.update_profile(['hello world'], [])
+		expect(profile2.Err).toBeDefined()
+	})
+	test('Should edit a post and a reply', async () => {
+		// create a post and a reply
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
+		const replyId = createdReply.Ok.reply_id
+
+		// edit post and reply
+		await actorBackendIc.edit_post(postId, 'hello world', 'description', [])
+		await actorBackendIc.edit_reply(replyId, 'hello world')
+
+		const post = await actorBackendIc.get_post(postId)
+		expect(post.Ok.title).toBe('hello world')
+		expect(post.Ok.edited_at.length).toBe(1)
+		expect(post.Ok.replies[0].text).toBe('hello world')
+		expect(post.Ok.replies[0].edited_at.length).toBe(1)
+
+		// check revisions
+		const postRevisions = await actorBackendIc.get_post_revisions(postId)
+		expect(postRevisions.Ok.map(r => r.title)).toEqual(['hello'])
+		const replyRevisions = await actorBackendIc.get_reply_revisions(replyId)
+		expect(replyRevisions.Ok.map(r => r.text)).toEqual(['hello'])
+
+		// edit by another profile
+		const identity = Ed25519KeyIdentity.generate()
+		const actor = Actor.createActor(childFactory, { agent: getAgent('http://127.0.0.1:8000', identity), canisterId: canisters.child.local })
+		await actor.create_profile({Ic: null})
+		const editedPost = await actor.edit_post(postId, 'hello', '', [])
+		expect(editedPost.Err).toBe('Caller is not the author')
+	})
+	test('Should create threaded replies', async () => {
+		// create a post and a reply
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
+		const replyId = createdReply.Ok.reply_id
+
+		// reply to reply
+		const createdChildReply = await actorBackendIc.create_reply(postId, 'hello', [replyId])
+		expect(createdChildReply.Ok.parent_reply_id[0]).toBe(replyId)
+		expect(createdChildReply.Ok.depth).toBe(1n)
+
+		const post = await actorBackendIc.get_post(postId)
+		expect(post.Ok.replies.map(r => r.reply_id)).toEqual([replyId, createdChildReply.Ok.reply_id])
+		expect(post.Ok.replies.map(r => r.depth)).toEqual([0n, 1n])
+
+		// max depth
+		const maxDepth = await actorBackendIc.get_max_reply_depth()
+		let parentReplyId = createdChildReply.Ok.reply_id
+		for (let i = 1n; i < maxDepth; i++) {
+			const createdNestedReply = await actorBackendIc.create_reply(postId, 'hello', [parentReplyId])
+			parentReplyId = createdNestedReply.Ok.reply_id
+		}
+		const createdDeepReply = await actorBackendIc.create_reply(postId, 'hello', [parentReplyId])
+		expect(createdDeepReply.Err).toBe('Maximum reply depth reached')
+	})
+	test('Should search posts and replies', async () => {
+		// create posts and replies
+		const keyword = `keyword${Date.now()}`
+		const createdPost = await actorBackendIc.create_post(`hello ${keyword}`, '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdPost1 = await actorBackendIc.create_post('hello', '', [], [])
+		const postId1 = createdPost1.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId1, `The ${keyword.toUpperCase()} in a reply`, [])
+		const replyId = createdReply.Ok.reply_id
+
+		// search
+		const searchResponse = await actorBackendIc.search(keyword, [], [])
+		expect(searchResponse.results.map(r => r.post.post_id)).toEqual([postId, postId1])
+		expect(searchResponse.results[1].replies[0].reply_id).toBe(replyId)
+
+		// paginate
+		const searchResponse1 = await actorBackendIc.search(keyword, [], [1n])
+		expect(searchResponse1.results.length).toBe(1)
+		const searchResponse2 = await actorBackendIc.search(keyword, searchResponse1.next_cursor, [1n])
+		expect(searchResponse2.results[0].post.post_id).toBe(postId1)
+		expect(searchResponse2.next_cursor.length).toBe(0)
+
+		// hidden content is only found by moderators
+		await actorBackendIc.update_post_status(postId, { Hidden: null })
+		await actorBackendIc.update_reply_status(replyId, { Hidden: null })
+		const searchResponse3 = await actorBackendSvm.search(keyword, [], [])
+		expect(searchResponse3.results.length).toBe(0)
+		const searchResponse4 = await actorBackendIc.search(keyword, [], [])
+		expect(searchResponse4.results.map(r => r.post.post_id)).toEqual([postId, postId1])
+		await actorBackendIc.update_post_status(postId, { Visible: null })
+		const searchResponse5 = await actorBackendSvm.search(keyword, [], [])
+		expect(searchResponse5.results.map(r => r.post.post_id)).toEqual([postId])
+	})
+	test('Should get posts page by sort', async () => {
+		// create posts
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdPost1 = await actorBackendIc.create_post('hello', '', [], [])
+		const postId1 = createdPost1.Ok.post_id
+
+		// newest
+		const page = await actorBackendIc.get_posts_page({Newest: null}, [], [1n])
+		expect(page.posts[0].post_id).toBe(postId1)
+		const page1 = await actorBackendIc.get_posts_page({Newest: null}, page.next_cursor, [1n])
+		expect(page1.posts[0].post_id).toBe(postId)
+
+		// last activity
+		await actorBackendIc.create_reply(postId, 'hello', [])
+		const page2 = await actorBackendIc.get_posts_page({LastActivity: null}, [], [1n])
+		expect(page2.posts[0].post_id).toBe(postId)
+
+		// most replied
+		const page3 = await actorBackendIc.get_posts_page({MostReplied: null}, [], [])
+		const replies = page3.posts.map(p => p.replies_count)
+		expect(replies).toEqual([...replies].sort((a, b) => Number(b - a)))
+	})
+	test('Should maintain post counters', async () => {
+		// create a post
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const posts = await actorBackendIc.get_posts()
+		expect(posts.find(p => p.post_id === postId).last_activity).toBe(0n)
+
+		// create a reply
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
+		const post = await actorBackendIc.get_post(postId)
+		const reply = post.Ok.replies.find(r => r.reply_id === createdReply.Ok.reply_id)
+		const posts1 = await actorBackendIc.get_posts()
+		expect(posts1.find(p => p.post_id === postId).last_activity).toBe(reply.timestamp)
+		expect(posts1.find(p => p.post_id === postId).replies_count).toBe(1n)
+
+		// hide and unhide the reply
+		await actorBackendIc.update_reply_status(createdReply.Ok.reply_id, {Hidden: null})
+		const posts2 = await actorBackendIc.get_posts()
+		expect(posts2.find(p => p.post_id === postId).replies_count).toBe(0n)
+		await actorBackendIc.update_reply_status(createdReply.Ok.reply_id, {Visible: null})
+		const posts3 = await actorBackendIc.get_posts()
+		expect(posts3.find(p => p.post_id === postId).replies_count).toBe(1n)
+
+		// like and unlike a post
+		const likedPost = await actorBackendIc.like_post(postId)
+		const page = await actorBackendIc.get_posts_page({MostLiked: null}, [], [])
+		const index = page.posts.findIndex(p => p.post_id === postId)
+		expect(page.posts[index].likes_count).toBe(1n)
+		await actorBackendIc.unlike_post(likedPost.Ok)
+		const page1 = await actorBackendIc.get_posts_page({MostLiked: null}, [], [])
+		expect(page1.posts.findIndex(p => p.post_id === postId)).toBeGreaterThanOrEqual(index)
+		const posts4 = await actorBackendIc.get_posts()
+		expect(posts4.find(p => p.post_id === postId).likes_count).toBe(0n)
+	})
+	test('Should organize posts in categories', async () => {
+		// create categories
+		const createdCategory = await actorBackendIc.create_category('general', '', 1n, false)
+		const categoryId = createdCategory.Ok
+		const createdCategory1 = await actorBackendIc.create_category('announcements', '', 0n, true)
+		const categoryId1 = createdCategory1.Ok
+		const categories = await actorBackendIc.get_categories()
+		expect(categories.findIndex(c => c.category_id === categoryId1)).toBeLessThan(categories.findIndex(c => c.category_id === categoryId))
+
+		// admin only category
+		const createdPost = await actorBackendEvm.create_post('hello', '', [categoryId1], [])
+		expect(createdPost.Err).toBe('Category is admin only')
+
+		// create a post in a category
+		const createdPost1 = await actorBackendEvm.create_post('hello', '', [categoryId], [])
+		const postId = createdPost1.Ok.post_id
+		expect(createdPost1.Ok.category_id).toEqual([categoryId])
+		const page = await actorBackendIc.get_category_posts_page(categoryId, {Newest: null}, [], [])
+		expect(page.Ok.posts.map(p => p.post_id)).toEqual([postId])
+
+		// move a post
+		await actorBackendIc.move_post(postId, [categoryId1])
+		const page1 = await actorBackendIc.get_category_posts_page(categoryId, {Newest: null}, [], [])
+		const page2 = await actorBackendIc.get_category_posts_page(categoryId1, {Newest: null}, [], [])
+		expect(page1.Ok.posts.length).toBe(0)
+		expect(page2.Ok.posts.map(p => p.post_id)).toEqual([postId])
+
+		// delete a category
+		await actorBackendIc.delete_category(categoryId1)
+		const post = await actorBackendIc.get_posts_page({Newest: null}, [], [1n])
+		expect(post.posts[0].category_id).toEqual([])
+	})
+	test('Should filter posts by tags', async () => {
+		// create tagged posts
+		const tag = `tag${Date.now()}`
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [[`#${tag.toUpperCase()}`, 'hello world']])
+		const postId = createdPost.Ok.post_id
+		expect(createdPost.Ok.tags).toEqual([tag, 'hello-world'])
+		const createdPost1 = await actorBackendIc.create_post('hello', '', [], [[tag]])
+		const postId1 = createdPost1.Ok.post_id
+
+		// invalid tags
+		const createdPost2 = await actorBackendIc.create_post('hello', '', [], [['a/b']])
+		expect(createdPost2.Err).toBeDefined()
+
+		// filter by one or more tags
+		const page = await actorBackendIc.get_tagged_posts_page([tag], {Newest: null}, [], [])
+		expect(page.Ok.posts.map(p => p.post_id)).toEqual([postId1, postId])
+		const page1 = await actorBackendIc.get_tagged_posts_page([tag, 'hello-world'], {Newest: null}, [], [])
+		expect(page1.Ok.posts.map(p => p.post_id)).toEqual([postId])
+
+		// edit tags
+		await actorBackendIc.edit_post(postId1, 'hello', '', [[]])
+		const page2 = await actorBackendIc.get_tagged_posts_page([tag], {Newest: null}, [], [])
+		expect(page2.Ok.posts.map(p => p.post_id)).toEqual([postId])
+
+		// popular tags
+		const tags = await actorBackendEvm.get_popular_tags([100n])
+		expect(tags.find(([t]) => t === tag)[1]).toBe(1n)
+		await actorBackendIc.update_post_status(postId, {Hidden: null})
+		const tags1 = await actorBackendEvm.get_popular_tags([100n])
+		expect(tags1.find(([t]) => t === tag)).toBeUndefined()
+	})
+	test('Should create a poll and vote', async () => {
+		// create a poll
+		const closesAt = BigInt(Date.now() + 60 * 60 * 1000) * 1_000_000n
+		const createdPoll = await actorBackendIc.create_poll('poll', '', ['yes', 'no'], closesAt, false)
+		const postId = createdPoll.Ok.post_id
+		const createdPoll1 = await actorBackendIc.create_poll('poll', '', ['yes'], closesAt, false)
+		expect(createdPoll1.Err).toBeDefined()
+
+		// vote
+		const vote = await actorBackendEvm.vote(postId, [0n, 1n])
+		expect(vote.Err).toBe('Invalid number of options')
+		const vote1 = await actorBackendEvm.vote(postId, [1n])
+		expect(vote1.Ok).toBe(null)
+		const vote2 = await actorBackendEvm.vote(postId, [0n])
+		expect(vote2.Err).toBe('Already voted')
+		await actorBackendIc.vote(postId, [1n])
+
+		// get results
+		const post = await actorBackendEvm.get_post(postId)
+		const poll = post.Ok.poll[0]
+		expect(poll.options.map(o => o.votes)).toEqual([0n, 2n])
+		expect(poll.voters).toBe(2n)
+		expect(poll.caller_votes).toEqual([1n])
+		expect(poll.closed).toBe(false)
+	})
+	test('Should toggle reactions on posts and replies', async () => {
+		// create a post and a reply
+		const createdPost = await actorBackendIc.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
+		const replyId = createdReply.Ok.reply_id
+
+		// react
+		const reacted = await actorBackendIc.react_to_post(postId, {Love: null})
+		expect(reacted.Ok).toBe(true)
+		await actorBackendEvm.react_to_post(postId, {Love: null})
+		await actorBackendEvm.react_to_reply(replyId, {Laugh: null})
+		const post = await actorBackendIc.get_post(postId)
+		expect(post.Ok.reactions).toEqual([{kind: {Love: null}, count: 2n, caller_reacted: true}])
+		expect(post.Ok.replies[0].reactions).toEqual([{kind: {Laugh: null}, count: 1n, caller_reacted: false}])
+
+		// likes are like reactions
+		const likedPost = await actorBackendIc.like_post(postId)
+		const post1 = await actorBackendIc.get_post(postId)
+		expect(post1.Ok.likes.map(([id]) => id)).toEqual([likedPost.Ok])
+		expect(post1.Ok.reactions.find(r => 'Like' in r.kind).count).toBe(1n)
+
+		// toggle off
+		const reacted1 = await actorBackendIc.react_to_post(postId, {Like: null})
+		expect(reacted1.Ok).toBe(false)
+		const post2 = await actorBackendIc.get_post(postId)
+		expect(post2.Ok.likes.length).toBe(0)
+		expect(post2.Ok.reactions.find(r => 'Like' in r.kind)).toBeUndefined()
+	})
+	test('Should send direct messages', async () => {
+		const evmAuthentication = {Evm: { address: await signerEvm.getAddress() }}
+		const icAuthentication = {Ic: { principal: identityIc.getPrincipal() }}
+
+		// send messages
+		const sentMessage = await actorBackendIc.send_message(evmAuthentication, 'hello')
+		expect(sentMessage.Ok).toBeDefined()
+		await actorBackendIc.send_message(evmAuthentication, 'world')
+		const sentMessage1 = await actorBackendIc.send_message(icAuthentication, 'hello')
+		expect(sentMessage1.Err).toBe('Cannot message yourself')
+
+		// get conversations and messages
+		const conversations = await actorBackendEvm.get_conversations()
+		const conversation = conversations.Ok.find(c => 'Ic' in c.participant)
+		expect(conversation.unread_count).toBe(2n)
+		expect(conversation.last_message[0].text).toBe('world')
+		const messages = await actorBackendEvm.get_messages(conversation.conversation_id, [], [1n])
+		expect(messages.Ok.messages.map(m => m.text)).toEqual(['world'])
+		const messages1 = await actorBackendEvm.get_messages(conversation.conversation_id, messages.Ok.next_cursor, [1n])
+		expect(messages1.Ok.messages.map(m => m.text)).toEqual(['hello'])
+		expect(messages1.Ok.next_cursor.length).toBe(0)
+		const messages2 = await actorBackendSvm.get_messages(conversation.conversation_id, [], [])
+		expect(messages2.Err).toBeDefined()
+
+		// mark as read
+		await actorBackendEvm.mark_conversation_read(conversation.conversation_id)
+		const conversations1 = await actorBackendEvm.get_conversations()
+		expect(conversations1.Ok.find(c => 'Ic' in c.participant).unread_count).toBe(0n)
+
+		// block
+		await actorBackendEvm.block_profile(icAuthentication)
+		const sentMessage2 = await actorBackendIc.send_message(evmAuthentication, 'hello')
+		expect(sentMessage2.Err).toBe('Recipient does not accept messages from caller')
+		await actorBackendEvm.unblock_profile(icAuthentication)
+		const sentMessage3 = await actorBackendIc.send_message(evmAuthentication, 'hello')
+		expect(sentMessage3.Ok).toBeDefined()
+	})
+	test('Should notify on replies and likes', async () => {
+		// clear notifications
+		await actorBackendEvm.mark_notifications_read([])
+
+		// reply and like
+		const createdPost = await actorBackendEvm.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId, 'hello', [])
+		await actorBackendIc.like_post(postId)
+		await actorBackendEvm.create_reply(postId, 'hello', [])
+
+		// get notifications
+		const count = await actorBackendEvm.get_unread_notifications_count()
+		expect(count.Ok).toBe(2n)
+		const notifications = await actorBackendEvm.get_notifications([], [1n])
+		expect('PostLike' in notifications.Ok.notifications[0].kind).toBe(true)
+		const notifications1 = await actorBackendEvm.get_notifications(notifications.Ok.next_cursor, [1n])
+		expect('Reply' in notifications1.Ok.notifications[0].kind).toBe(true)
+		expect(notifications1.Ok.notifications[0].reply_id).toEqual([createdReply.Ok.reply_id])
+
+		// mark as read
+		await actorBackendEvm.mark_notifications_read([[notifications.Ok.notifications[0].notification_id]])
+		const count1 = await actorBackendEvm.get_unread_notifications_count()
+		expect(count1.Ok).toBe(1n)
+		await actorBackendEvm.mark_notifications_read([])
+		const count2 = await actorBackendEvm.get_unread_notifications_count()
+		expect(count2.Ok).toBe(0n)
+	})
+	test('Should resolve mentions in posts and replies', async () => {
+		const address = await signerEvm.getAddress()
+
+		// mention by address
+		const createdPost = await actorBackendIc.create_post('hello', `hi @${address.toLowerCase()}`, [], [])
+		const postId = createdPost.Ok.post_id
+		const createdReply = await actorBackendIc.create_reply(postId, `hi again @${address} and email@${address}`, [])
+		expect(createdReply.Ok.mentions.length).toBe(1)
+		expect(createdReply.Ok.mentions[0].authentication.Evm.address).toBe(address)
+		const post = await actorBackendIc.get_post(postId)
+		expect(post.Ok.mentions[0].text).toBe(`@${address.toLowerCase()}`)
+
+		// unknown mentions are ignored
+		const createdReply1 = await actorBackendIc.create_reply(postId, '@nobody_here', [])
+		expect(createdReply1.Ok.mentions.length).toBe(0)
+
+		// posts mentioning the caller
+		const mentionedPosts = await actorBackendEvm.get_mentioned_posts_page({ Newest: null }, [], [])
+		expect(mentionedPosts.Ok.posts.map(p => p.post_id)).toContain(postId)
+		const notifications = await actorBackendEvm.get_notifications([], [1n])
+		expect('Mention' in notifications.Ok.notifications[0].kind).toBe(true)
+	})
+	test('Should render sanitized markdown', async () => {
+		// render markdown
+		const createdPost = await actorBackendEvm.create_post('hello', '# Title\n\n**bold** [link](https://example.com)', [], [])
+		const postId = createdPost.Ok.post_id
+		const post = await actorBackendEvm.get_post(postId)
+		expect(post.Ok.description).toBe('# Title\n\n**bold** [link](https://example.com)')
+		expect(post.Ok.description_html).toBe('<h1>Title</h1><p><strong>bold</strong> <a href="https://example.com" rel="nofollow noopener">link</a></p>')
+
+		// escape html and unsafe links
+		const createdReply = await actorBackendEvm.create_reply(postId, '<script>alert(1)</script> [click](javascript:void)', [])
+		expect(createdReply.Ok.text_html).toBe('<p>&lt;script&gt;alert(1)&lt;/script&gt; click</p>')
+
+		// render on edit
+		await actorBackendEvm.edit_reply(createdReply.Ok.reply_id, '*edited*')
+		const post1 = await actorBackendEvm.get_post(postId)
+		expect(post1.Ok.replies[0].text_html).toBe('<p><em>edited</em></p>')
+	})
+	test('Should upload attachments and remove them with the post', async () => {
+		// upload
+		const content = Uint8Array.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, ...new TextEncoder().encode('png')])
+		const attachmentResult = await actorBackendEvm.create_attachment('image/png', BigInt(content.length))
+		const attachmentId = attachmentResult.Ok
+		await actorBackendEvm.upload_attachment_chunk(attachmentId, content.slice(0, 8))
+		const incompleteResult = await actorBackendEvm.commit_attachment(attachmentId)
+		expect(incompleteResult.Err).toBe('Attachment upload is incomplete')
+		await actorBackendEvm.upload_attachment_chunk(attachmentId, content.slice(8))
+		const committedResult = await actorBackendEvm.commit_attachment(attachmentId)
+		expect(committedResult.Ok.url).toBe(`/uploads/${attachmentId}`)
+		const upload = await getAsset(actorBackendEvm, committedResult.Ok.url)
+		expect(upload.status).toBe(200)
+		expect(upload.headers['Content-Type']).toBe('image/png')
+		expect(upload.body).toBe(new TextDecoder().decode(content))
+
+		// validate content type and quota
+		const invalidResult = await actorBackendEvm.create_attachment('text/html', 10n)
+		expect(invalidResult.Err).toBe('Content type is not allowed')
+		const svg = new TextEncoder().encode('<svg xmlns="http://www.w3.org/2000/svg"></svg>')
+		const svgResult = await actorBackendEvm.create_attachment('image/png', BigInt(svg.length))
+		const svgChunkResult = await actorBackendEvm.upload_attachment_chunk(svgResult.Ok, svg)
+		expect(svgChunkResult.Err).toBe('Content does not match the content type')
+		await actorBackendEvm.delete_attachment(svgResult.Ok)
+		const attachments = await actorBackendEvm.get_attachments()
+		expect(attachments.Ok[1]).toBe(BigInt(content.length))
+
+		// attach to post
+		const createdPost = await actorBackendEvm.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		const attachedResult = await actorBackendSvm.attach_to_post(attachmentId, postId)
+		expect(attachedResult.Err).toBeDefined()
+		await actorBackendEvm.attach_to_post(attachmentId, postId)
+		const post = await actorBackendEvm.get_post(postId)
+		expect(post.Ok.attachments[0].attachment_id).toBe(attachmentId)
+
+		// purge post
+		await actorBackendIc.purge_post(postId)
+		const post1 = await actorBackendEvm.get_post(postId)
+		expect(post1.Err).toBeDefined()
+		const attachments1 = await actorBackendEvm.get_attachments()
+		expect(attachments1.Ok[0].length).toBe(0)
+		const upload1 = await getAsset(actorBackendEvm, committedResult.Ok.url)
+		expect(upload1.body).not.toEqual(upload.body)
+	})
+	test('Should bookmark posts', async () => {
+		// bookmark
+		const createdPost = await actorBackendEvm.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		await actorBackendSvm.bookmark_post(postId)
+		const bookmarkedResult = await actorBackendSvm.bookmark_post(postId)
+		expect(bookmarkedResult.Err).toBe('Post is already bookmarked')
+		const bookmarks = await actorBackendSvm.get_bookmarks({ Newest: null }, [], [])
+		expect(bookmarks.Ok.posts[0].post_id).toBe(postId)
+
+		// hidden posts are skipped
+		await actorBackendIc.update_post_status(postId, { Hidden: null })
+		const bookmarks1 = await actorBackendSvm.get_bookmarks({ Newest: null }, [], [])
+		expect(bookmarks1.Ok.posts.map(p => p.post_id)).not.toContain(postId)
+		await actorBackendIc.update_post_status(postId, { Visible: null })
+
+		// unbookmark
+		await actorBackendSvm.unbookmark_post(postId)
+		const bookmarks2 = await actorBackendSvm.get_bookmarks({ Newest: null }, [], [])
+		expect(bookmarks2.Ok.posts.map(p => p.post_id)).not.toContain(postId)
+	})
+	test('Should build a feed from followed profiles and subscribed posts', async () => {
+		const evmAuthentication = { Evm: { address: await signerEvm.getAddress() } }
+
+		// follow
+		await actorBackendSvm.follow_profile(evmAuthentication)
+		const profile = await actorBackendSvm.get_profile_by_auth(evmAuthentication)
+		expect(profile[0].total_followers).toBe(1n)
+		const followedPost = await actorBackendEvm.create_post('hello', '', [], [])
+
+		// subscribe
+		const subscribedPost = await actorBackendIc.create_post('hello', '', [], [])
+		await actorBackendSvm.subscribe_post(subscribedPost.Ok.post_id)
+		const otherPost = await actorBackendIc.create_post('hello', '', [], [])
+
+		// get feed
+		const feed = await actorBackendSvm.get_feed([], [])
+		const postIds = feed.Ok.posts.map(p => p.post_id)
+		expect(postIds).toContain(followedPost.Ok.post_id)
+		expect(postIds).toContain(subscribedPost.Ok.post_id)
+		expect(postIds).not.toContain(otherPost.Ok.post_id)
+
+		// unfollow and unsubscribe
+		await actorBackendSvm.unfollow_profile(evmAuthentication)
+		await actorBackendSvm.unsubscribe_post(subscribedPost.Ok.post_id)
+		const feed1 = await actorBackendSvm.get_feed([], [])
+		expect(feed1.Ok.posts.length).toBe(0)
+	})
+	test('Should pin, lock and announce posts', async () => {
+		const createdPost = await actorBackendEvm.create_post('hello', '', [], [])
+		const postId = createdPost.Ok.post_id
+		await actorBackendEvm.create_post('hello', '', [], [])
+
+		// admin only
+		const flags = { pinned: true, locked: true, announcement: true }
+		const updatedResult = await actorBackendEvm.update_post_flags(postId, flags)
+		expect(updatedResult.Err).toBe('Caller does not have permission')
+		await actorBackendIc.update_post_flags(postId, flags)
+
+		// pinned on the first page without counting towards the limit
+		const page = await actorBackendEvm.get_posts_page({ Newest: null }, [], [1n])
+		expect(page.pinned_posts.map(p => p.post_id)).toContain(postId)
+		expect(page.pinned_posts.find(p => p.post_id === postId).flags).toEqual(flags)
+		expect(page.posts.length).toBe(1)
+		expect(page.posts.map(p => p.post_id)).not.toContain(postId)
+		const page1 = await actorBackendEvm.get_posts_page({ Newest: null }, page.next_cursor, [])
+		expect(page1.posts.map(p => p.post_id)).not.toContain(postId)
+		expect(page1.pinned_posts).toEqual([])
+
+		// locked
+		const createdReply = await actorBackendEvm.create_reply(postId, 'hello', [])
+		expect(createdReply.Err).toBe('Post is locked')
+		await actorBackendIc.update_post_flags(postId, { pinned: false, locked: false, announcement: false })
+	})
+	test('Should save, schedule and publish drafts', async () => {
+		// save draft
+		const savedDraft = await actorBackendEvm.save_draft([], 'draft', 'hello', [], [])
+		const draftId = savedDraft.Ok.draft_id
+		const updatedDraft = await actorBackendEvm.save_draft([draftId], 'draft', 'hello world', [], [])
+		expect(updatedDraft.Ok.description).toBe('hello world')
+		const otherDraft = await actorBackendSvm.save_draft([draftId], 'draft', 'hello', [], [])
+		expect(otherDraft.Err).toBe('Draft does not exist')
+
+		// schedule and cancel
+		const publishAt = BigInt(Date.now() + 60 * 60 * 1000) * 1_000_000n
+		await actorBackendEvm.schedule_draft(draftId, publishAt)
+		const scheduledDrafts = await actorBackendEvm.get_scheduled_drafts()
+		expect(scheduledDrafts.Ok[0].publish_at).toEqual([publishAt])
+		await actorBackendEvm.cancel_scheduled_draft(draftId)
+		const scheduledDrafts1 = await actorBackendEvm.get_scheduled_drafts()
+		expect(scheduledDrafts1.Ok.length).toBe(0)
+
+		// publish
+		const publishedPost = await actorBackendEvm.publish_draft(draftId)
+		expect(publishedPost.Ok.description).toBe('hello world')
+		const drafts = await actorBackendEvm.get_drafts()
+		expect(drafts.Ok.map(d => d.draft_id)).not.toContain(draftId)
+	})
+	test('Should publish scheduled drafts when the timer fires', async () => {
+		const svmAddress = signerSvm.publicKey.toString()
+		const savedDraft = await actorBackendSvm.save_draft([], 'scheduled draft', 'hello', [], [])
+		const draftId = savedDraft.Ok.draft_id
+
+		// the timer turns the draft into a post
+		const publishAt = BigInt(Date.now() + 4000) * 1_000_000n
+		const scheduledResult = await actorBackendSvm.schedule_draft(draftId, publishAt)
+		expect(scheduledResult.Ok).toBe(null)
+		await sleep(Number(publishAt / 1_000_000n) - Date.now() + 2000)
+		const drafts = await actorBackendSvm.get_drafts()
+		expect(drafts.Ok.map(d => d.draft_id)).not.toContain(draftId)
+		const userPosts = await actorBackendSvm.get_most_recent_posts({ Svm: { address: svmAddress } })
+		expect(userPosts.Ok.map(p => p.title)).toContain('scheduled draft')
+	})
+	test('Should update robots.txt', async () => {
+		// default robots.txt points to the sitemap
+		const defaultRobotsTxt = await actorBackendIc.get_robots_txt()
+		expect(defaultRobotsTxt).toContain('/sitemap.xml')
+
+		// only admins can update it
+		const updatedByUser = await actorBackendEvm.update_robots_txt(['User-agent: *\nDisallow: /'])
+		expect(updatedByUser.Err).toBe('Caller does not have permission')
+
+		// custom robots.txt
+		const robotsTxt = 'User-agent: *\nDisallow: /'
+		await actorBackendIc.update_robots_txt([robotsTxt])
+		expect(await actorBackendIc.get_robots_txt()).toBe(robotsTxt)
+
+		// restore default
+		await actorBackendIc.update_robots_txt([])
+		expect(await actorBackendIc.get_robots_txt()).toBe(defaultRobotsTxt)
+	})
+	test('Should assign and revoke roles', async () => {
+		const evmAuthentication = { Evm: { address: await signerEvm.getAddress() } }
+
+		// only admins manage roles
+		const assignedByUser = await actorBackendSvm.assign_role(evmAuthentication, { Moderator: null })
+		expect(assignedByUser.Err).toBe('Caller does not have permission')
+		const assignedAdmin = await actorBackendIc.assign_role(evmAuthentication, { Admin: null })
+		expect(assignedAdmin.Err).toBe('Admin role is managed with tokens')
+
+		// moderators can hide posts but not manage categories
+		const createdPost = await actorBackendSvm.create_post('hello', '', [], [])
+		await actorBackendIc.assign_role(evmAuthentication, { Moderator: null })
+		const permissions = await actorBackendEvm.get_permissions()
+		expect(permissions).toEqual([{ CreateContent: null }, { HideContent: null }, { PinPosts: null }])
+		const hiddenResult = await actorBackendEvm.update_post_status(createdPost.Ok.post_id, { Hidden: null })
+		expect(hiddenResult.Ok).toBe(null)
+		const categoryResult = await actorBackendEvm.create_category('category', '', 0n, false)
+		expect(categoryResult.Err).toBe('Caller does not have permission')
+		await actorBackendIc.revoke_role(evmAuthentication, { Moderator: null })
+
+		// muted profiles cannot post
+		await actorBackendIc.assign_role(evmAuthentication, { Muted: null })
+		const mutedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(mutedPost.Err).toBe('Caller does not have permission')
+		const revokedResult = await actorBackendIc.revoke_role(evmAuthentication, { Muted: null })
+		expect(revokedResult.Ok).toBe(null)
+		const revokedAgain = await actorBackendIc.revoke_role(evmAuthentication, { Muted: null })
+		expect(revokedAgain.Err).toBe('Profile does not have the role')
+	})
+	test('Should ban and mute profiles', async () => {
+		const evmAuthentication = { Evm: { address: await signerEvm.getAddress() } }
+		const expiresAt = BigInt(Date.now() + 60 * 60 * 1000) * 1_000_000n
+
+		// only moderators sanction profiles
+		const bannedByUser = await actorBackendSvm.ban_profile(evmAuthentication, 'spam', [])
+		expect(bannedByUser.Err).toBe('Caller does not have permission')
+		const emptyReason = await actorBackendIc.ban_profile(evmAuthentication, ' ', [])
+		expect(emptyReason.Err).toBe('Reason should be between 1 and 500 characters')
+
+		// banned profiles cannot post or like until the ban is lifted
+		const createdPost = await actorBackendSvm.create_post('hello', '', [], [])
+		const banResult = await actorBackendIc.ban_profile(evmAuthentication, 'spam', [expiresAt])
+		const bannedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(bannedPost.Err).toBe('Caller does not have permission')
+		const bannedLike = await actorBackendEvm.like_post(createdPost.Ok.post_id)
+		expect(bannedLike.Err).toBe('Caller does not have permission')
+		const callerSanctions = await actorBackendEvm.get_caller_sanctions()
+		expect(callerSanctions.Ok.map((sanction) => [sanction.kind, sanction.reason, sanction.expires_at])).toEqual([[{ Ban: null }, 'spam', [expiresAt]]])
+		const [bannedProfile] = await actorBackendSvm.get_profile_by_auth(evmAuthentication)
+		expect(bannedProfile.roles).toContainEqual({ Banned: null })
+		await actorBackendIc.lift_sanction(banResult.Ok)
+		const unbannedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(unbannedPost.Ok).toBeTruthy()
+
+		// muted profiles cannot post and lifted sanctions are removed
+		const muteResult = await actorBackendIc.mute_profile(evmAuthentication, 'off topic', [])
+		const mutedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(mutedPost.Err).toBe('Caller does not have permission')
+		const [mutedProfile] = await actorBackendSvm.get_profile_by_auth(evmAuthentication)
+		expect(mutedProfile.roles).toContainEqual({ Muted: null })
+		const sanctions = await actorBackendIc.get_sanctions(evmAuthentication)
+		expect(sanctions.Ok.map((sanction) => sanction.reason)).toEqual(['off topic'])
+		const liftedResult = await actorBackendIc.lift_sanction(muteResult.Ok)
+		expect(liftedResult.Ok).toBe(null)
+		const liftedAgain = await actorBackendIc.lift_sanction(muteResult.Ok)
+		expect(liftedAgain.Err).toBe('Sanction does not exist')
+		const [unmutedProfile] = await actorBackendSvm.get_profile_by_auth(evmAuthentication)
+		expect(unmutedProfile.roles).not.toContainEqual({ Muted: null })
+	})
+	test('Should keep bans across principals and lift them on expiry', async () => {
+		const evmAddress = await signerEvm.getAddress()
+
+		// bans match any casing of the address and new principals of the same wallet
+		const banResult = await actorBackendIc.ban_profile({ Evm: { address: evmAddress.toLowerCase() } }, 'spam', [])
+		identityEvm = Ed25519KeyIdentity.generate()
+		const agentEvm = getAgent('http://127.0.0.1:8000', identityEvm)
+		actorBackendEvm = Actor.createActor(childFactory, { agent: agentEvm, canisterId: canisters.child.local })
+		const { signature, loginMessageHash } = await getSignatureAndMessage(signerEvm, identityEvm.getPrincipal())
+		await actorBackendEvm.create_profile({ Evm: { signature, message: loginMessageHash } })
+		const bannedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(bannedPost.Err).toBe('Caller does not have permission')
+		const sanctions = await actorBackendIc.get_sanctions({ Evm: { address: evmAddress } })
+		expect(sanctions.Ok.map((sanction) => sanction.authentication)).toEqual([{ Evm: { address: evmAddress } }])
+		await actorBackendIc.lift_sanction(banResult.Ok)
+
+		// temporary bans stop applying once they expire
+		const expiresAt = BigInt(Date.now() + 4000) * 1_000_000n
+		await actorBackendIc.ban_profile({ Evm: { address: evmAddress } }, 'spam', [expiresAt])
+		const temporaryBannedPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(temporaryBannedPost.Err).toBe('Caller does not have permission')
+		await sleep(Number(expiresAt / 1_000_000n) - Date.now() + 2000)
+		const expiredBanPost = await actorBackendEvm.create_post('hello', '', [], [])
+		expect(expiredBanPost.Ok).toBeTruthy()
+		const callerSanctions = await actorBackendEvm.get_caller_sanctions()
+		expect(callerSanctions.Ok).toEqual([])
+	})
+	test('Should serve post pages for search engines', async () => {
+		const createdPost = await actorBackendSvm.create_post('Hello <b>"world"</b>', 'A post & a <i>description</i>', [], [])
+		const postId = createdPost.Ok.post_id
+
+		// title and meta tags are escaped
+		const page = await getAsset(actorBackendSvm, `/post/${postId}`)
+		expect(page.status).toBe(200)
+		expect(page.body).toContain('<title>Hello &lt;b&gt;&quot;world&quot;&lt;/b&gt;</title>')
+		expect(page.body).toContain('<meta property="og:type" content="article" />')
+		expect(page.body).toContain('<meta property="og:title" content="Hello &lt;b&gt;&quot;world&quot;&lt;/b&gt;" />')
+		expect(page.body).toContain('<meta property="og:description" content="A post &amp; a &lt;i&gt;description&lt;/i&gt;" />')
+		expect(page.body).not.toContain('<b>"world"</b>')
+
+		// replies are rendered oldest first
+		for (const text of ['first reply', 'second reply', 'third reply']) {
+			await actorBackendSvm.create_reply(postId, text, [])
+		}
+		const repliesPage = await getAsset(actorBackendSvm, `/post/${postId}`)
+		const positions = ['first reply', 'second reply', 'third reply'].map(text => repliesPage.body.indexOf(text))
+		expect(positions.every(position => position > 0)).toBe(true)
+		expect(positions).toEqual([...positions].sort((a, b) => a - b))
+
+		// hidden posts are removed from the pages
+		await actorBackendIc.update_post_status(postId, { Hidden: null })
+		const hiddenPage = await getAsset(actorBackendSvm, `/post/${postId}`)
+		expect(hiddenPage.body).not.toContain('og:title')
+	})
+	test('Should serve feeds of posts and replies', async () => {
+		const title = 'Feed <b>&</b>'
+		const escapedTitle = 'Feed &lt;b&gt;&amp;&lt;/b&gt;'
+		const createdPost = await actorBackendSvm.create_post(title, 'feed description', [], [])
+		const postId = createdPost.Ok.post_id
+		await actorBackendIc.create_reply(postId, 'feed reply', [])
+
+		// community feeds with escaped titles
+		const rss = await getAsset(actorBackendSvm, '/feed.xml')
+		expect(rss.headers['Content-Type']).toBe('application/rss+xml')
+		expect(rss.body).toContain(`<title>${escapedTitle}</title>`)
+		const atom = await getAsset(actorBackendSvm, '/atom.xml')
+		expect(atom.headers['Content-Type']).toBe('application/atom+xml')
+		expect(atom.body).toContain(`<title>${escapedTitle}</title>`)
+		const jsonFeed = await getAsset(actorBackendSvm, '/feed.json')
+		expect(jsonFeed.headers['Content-Type']).toBe('application/feed+json')
+		expect(JSON.parse(jsonFeed.body).items.map((item) => item.title)).toContain(title)
+
+		// replies feed of the post
+		const postFeed = await getAsset(actorBackendSvm, `/post/${postId}/feed.xml`)
+		expect(postFeed.headers['Content-Type']).toBe('application/rss+xml')
+		expect(postFeed.body).toContain(`<title>Re: ${escapedTitle}</title>`)
+		expect(postFeed.body).toContain('feed reply')
+
+		// hidden posts are dropped from the feeds
+		await actorBackendIc.update_post_status(postId, { Hidden: null })
+		expect((await getAsset(actorBackendSvm, '/feed.xml')).body).not.toContain(escapedTitle)
+		expect((await getAsset(actorBackendSvm, '/atom.xml')).body).not.toContain(escapedTitle)
+		expect(JSON.parse((await getAsset(actorBackendSvm, '/feed.json')).body).items.map((item) => item.title)).not.toContain(title)
+		expect((await getAsset(actorBackendSvm, `/post/${postId}/feed.xml`)).body).not.toContain('feed reply')
+
+		// feeds are titled with the community name
+		const namedByUser = await actorBackendSvm.update_community_name(['Rust & friends'])
+		expect(namedByUser.Err).toBe('Caller does not have permission')
+		await actorBackendIc.update_community_name(['Rust & friends'])
+		expect(await actorBackendSvm.get_community_name()).toEqual(['Rust & friends'])
+		expect((await getAsset(actorBackendSvm, '/feed.xml')).body).toContain('<channel><title>Rust &amp; friends</title>')
+		expect(JSON.parse((await getAsset(actorBackendSvm, '/feed.json')).body).title).toBe('Rust & friends')
+		await actorBackendIc.update_community_name([])
+		expect((await getAsset(actorBackendSvm, '/feed.xml')).body).toContain('<channel><title>Community</title>')
+	})
+	test('Should serve the json api', async () => {
+		const createdPost = await actorBackendSvm.create_post('api post', 'api description', [], [])
+		const postId = createdPost.Ok.post_id
+
+		// requests are upgraded to update calls unless uncertified responses are requested
+		const upgraded = await getAsset(actorBackendSvm, '/api/v1/posts')
+		expect(upgraded.upgrade).toEqual([true])
+		const certified = await getAssetUpdate(actorBackendSvm, '/api/v1/posts?sort=newest&limit=1')
+		expect(certified.status).toBe(200)
+		expect(certified.headers['Content-Type']).toBe('application/json')
+		expect(certified.headers['Access-Control-Allow-Origin']).toBe('*')
+		expect(JSON.parse(certified.body).posts.map((post) => post.post_id)).toEqual([postId.toString()])
+		const uncertified = await getAsset(actorBackendSvm, '/api/v1/posts?limit=1&uncertified=true')
+		expect(uncertified.status).toBe(200)
+		expect(uncertified.upgrade).toEqual([])
+		expect(JSON.parse(uncertified.body).posts.map((post) => post.post_id)).toEqual([postId.toString()])
+
+		// posts and profiles
+		const post = await getAsset(actorBackendSvm, `/api/v1/posts/${postId}?uncertified=true`)
+		expect(JSON.parse(post.body).title).toBe('api post')
+		const profile = await getAsset(actorBackendSvm, `/api/v1/profiles/${signerSvm.publicKey.toString()}?uncertified=true`)
+		expect(profile.status).toBe(200)
+		expect(JSON.parse(profile.body).authentication).toEqual({ Svm: { address: signerSvm.publicKey.toString() } })
+
+		// invalid requests
+		const invalidPostId = await getAsset(actorBackendSvm, '/api/v1/posts/abc?uncertified=true')
+		expect([invalidPostId.status, JSON.parse(invalidPostId.body).error]).toEqual([400, 'Invalid post id'])
+		const invalidSort = await getAsset(actorBackendSvm, '/api/v1/posts?sort=oldest&uncertified=true')
+		expect([invalidSort.status, JSON.parse(invalidSort.body).error]).toEqual([400, 'Invalid sort'])
+		const missingPost = await getAsset(actorBackendSvm, '/api/v1/posts/1?uncertified=true')
+		expect(missingPost.status).toBe(404)
+		const missingRoute = await getAsset(actorBackendSvm, '/api/v1/replies?uncertified=true')
+		expect(missingRoute.status).toBe(404)
+		const postMethod = await getAssetUpdate(actorBackendSvm, '/api/v1/posts', 'POST')
+		expect(postMethod.status).toBe(405)
+
+		// preflight requests are answered from the query call
+		const preflight = await getAsset(actorBackendSvm, '/api/v1/posts', 'OPTIONS')
+		expect(preflight.status).toBe(204)
+		expect(preflight.upgrade).toEqual([])
+		expect(preflight.headers['Access-Control-Allow-Methods']).toBe('GET, OPTIONS')
+	})
+	test('Should only let admins sanction moderators and lift their sanctions', async () => {
+		const evmAuthentication = { Evm: { address: await signerEvm.getAddress() } }
+		const svmAuthentication = { Svm: { address: signerSvm.publicKey.toString() } }
+		await actorBackendIc.assign_role(evmAuthentication, { Moderator: null })
+		await actorBackendIc.assign_role(svmAuthentication, { Moderator: null })
+
+		// moderators cannot sanction each other
+		const bannedByModerator = await actorBackendSvm.ban_profile(evmAuthentication, 'spam', [])
+		expect(bannedByModerator.Err).toBe('Only admins can sanction moderators')
+		const mutedByModerator = await actorBackendEvm.mute_profile(svmAuthentication, 'spam', [])
+		expect(mutedByModerator.Err).toBe('Only admins can sanction moderators')
+
+		// nor lift the sanctions of an admin
+		await actorBackendIc.revoke_role(evmAuthentication, { Moderator: null })
+		const muteResult = await actorBackendIc.mute_profile(evmAuthentication, 'off topic', [])
+		const liftedByModerator = await actorBackendSvm.lift_sanction(muteResult.Ok)
+		expect(liftedByModerator.Err).toBe('Only admins can lift sanctions issued by an admin')
+		const liftedByAdmin = await actorBackendIc.lift_sanction(muteResult.Ok)
+		expect(liftedByAdmin.Ok).toBe(null)
+		await actorBackendIc.revoke_role(svmAuthentication, { Moderator: null })
+	})
 })
@@ -132,4 +132,44 @@ describe.only('Testing with done', () => {
 		const resRemoveTrack = await actorParent.remove_track(tractUpgrade)
 		expect(resRemoveTrack.Ok).toBeDefined()
 	})
+
+	test('Should keep attachments on upgrade', async () => {
+
+		// create child
+		if (canisterIds.ledger) {
+			const accountId = getAccountId(canisterIds.parent.local, principal)
+			await transferIcpToAccount(accountId)
+		}
+		const childPrincipalId = await actorParent.create_child().then(p => p.Ok.toString())
+		const actorChild = Actor.createActor(childFactory, { agent, canisterId: childPrincipalId })
+
+		// upload attachment
+		await actorChild.create_profile({Ic: null})
+		const content = Uint8Array.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, ...new TextEncoder().encode('kept on upgrade')])
+		const attachmentResult = await actorChild.create_attachment('image/png', BigInt(content.length))
+		const attachmentId = attachmentResult.Ok
+		await actorChild.upload_attachment_chunk(attachmentId, content)
+		const committedResult = await actorChild.commit_attachment(attachmentId)
+		expect(committedResult.Ok).toBeDefined()
+
+		// upload upgrade (0.0.2)
+		spawnSync('node', ['./src/_parent/upload-upgrade.js', '--version', '0.0.2' ,'--description', 'description for 0.0.2', '--upgradeFromVersion', '0.0.1', '--upgradeFromTrack', 'default', '--track', 'default', '--path' ,'./build/child-test' ] ,{cwd: process.cwd(), stdio: 'inherit'})
+
+		// upgrade child (0.0.2)
+		const resNextUpgrades = await actorChild.get_next_upgrades()
+		const [ upgrade ] = resNextUpgrades.Ok
+		expect(upgrade).toBeDefined()
+		await actorChild.upgrade_canister(upgrade.version, upgrade.track.name)
+
+		await sleep(UPGRADE_DELAY)
+
+		// check attachment
+		const metadata = await actorChild.get_metadata()
+		expect(metadata.Ok.version).toBe(upgrade.version)
+		const attachments = await actorChild.get_attachments()
+		expect(attachments.Ok[0].map(a => a.attachment_id)).toEqual([attachmentId])
+		const response = await actorChild.http_request({ url: committedResult.Ok.url, method: 'GET', body: [], headers: [] })
+		expect(response.status_code).toBe(200)
+		expect(Uint8Array.from(response.body)).toEqual(content)
+	})
 })